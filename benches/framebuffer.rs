@@ -0,0 +1,76 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Byte wise fills against drawing the same pixels one by one, run with `cargo bench`.
+
+use embedded_graphics::{Pixel, pixelcolor::BinaryColor, prelude::*, primitives::Rectangle};
+use kywy::framebuffer::{DisplayRotation, Framebuffer};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ROUNDS: u32 = 2000;
+
+fn time(name: &str, fb: &mut Framebuffer, mut draw: impl FnMut(&mut Framebuffer, u32)) -> Duration {
+    let start = Instant::now();
+    for round in 0..ROUNDS {
+        draw(black_box(&mut *fb), round);
+    }
+    let per_round = start.elapsed() / ROUNDS;
+    println!("{name:<40} {per_round:>10.2?}");
+    per_round
+}
+
+fn color(round: u32) -> BinaryColor {
+    BinaryColor::from(round.is_multiple_of(2))
+}
+
+fn compare(name: &str, rotation: DisplayRotation, area: Rectangle) {
+    let mut fb = Framebuffer::new();
+    fb.set_rotation(rotation);
+    println!(
+        "{name}, {rotation:?}, {}x{}",
+        area.size.width, area.size.height
+    );
+
+    let pixels = time("  draw_iter", &mut fb, |fb, round| {
+        fb.draw_iter(area.points().map(|p| Pixel(p, color(round))))
+            .unwrap();
+    });
+    let solid = time("  fill_solid", &mut fb, |fb, round| {
+        fb.fill_solid(&area, color(round)).unwrap();
+    });
+    let contiguous = time("  fill_contiguous", &mut fb, |fb, round| {
+        fb.fill_contiguous(&area, area.points().map(|p| color(round + p.x as u32)))
+            .unwrap();
+    });
+    let pattern = time("  draw_iter, same pattern", &mut fb, |fb, round| {
+        let pixels = area.points().map(|p| Pixel(p, color(round + p.x as u32)));
+        fb.draw_iter(pixels).unwrap();
+    });
+    println!(
+        "  fill_solid {:.1}x, fill_contiguous {:.1}x faster than pixels\n",
+        pixels.as_secs_f64() / solid.as_secs_f64(),
+        pattern.as_secs_f64() / contiguous.as_secs_f64()
+    );
+}
+
+fn main() {
+    let screen = Rectangle::new(Point::zero(), Size::new(144, 168));
+    let sprite = Rectangle::new(Point::new(13, 27), Size::new(64, 64));
+    compare("whole screen", DisplayRotation::Rotate0, screen);
+    compare("64x64 at an odd offset", DisplayRotation::Rotate0, sprite);
+    compare("64x64 at an odd offset", DisplayRotation::Rotate90, sprite);
+
+    let mut fb = Framebuffer::new();
+    let clear = time("clear", &mut fb, |fb, round| {
+        fb.clear(color(round)).unwrap()
+    });
+    let pixels = time("clear, pixel by pixel", &mut fb, |fb, round| {
+        fb.draw_iter(screen.points().map(|p| Pixel(p, color(round))))
+            .unwrap();
+    });
+    println!(
+        "  clear {:.1}x faster than pixels",
+        pixels.as_secs_f64() / clear.as_secs_f64()
+    );
+}
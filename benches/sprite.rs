@@ -0,0 +1,132 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Drawing every frame of the 64x64 monster sheets, run with `cargo bench`.
+//! Compares scanning the whole BMP for each draw (how sprites used to be drawn),
+//! reading only the frame pixel by pixel, and `Sprite::draw`, which copies whole
+//! bytes. The two baselines are always untransformed.
+
+use embedded_graphics::{
+    Pixel, image::GetPixel, pixelcolor::BinaryColor, prelude::*, primitives::Rectangle,
+};
+use kywy::engine::sprite::{BlendMode, Rotation, SpriteOptions, SpriteSheet};
+use kywy::framebuffer::Framebuffer;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use tinybmp::Bmp;
+
+const ROUNDS: u32 = 20;
+const SPRITE: Size = Size::new(64, 64);
+
+const SHEETS: [&[u8]; 10] = [
+    include_bytes!("../examples/Art Assets/monsters/electric.bmp"),
+    include_bytes!("../examples/Art Assets/monsters/electric2.bmp"),
+    include_bytes!("../examples/Art Assets/monsters/fire.bmp"),
+    include_bytes!("../examples/Art Assets/monsters/fire2.bmp"),
+    include_bytes!("../examples/Art Assets/monsters/plant.bmp"),
+    include_bytes!("../examples/Art Assets/monsters/plant2.bmp"),
+    include_bytes!("../examples/Art Assets/monsters/stone.bmp"),
+    include_bytes!("../examples/Art Assets/monsters/stone2.bmp"),
+    include_bytes!("../examples/Art Assets/monsters/water.bmp"),
+    include_bytes!("../examples/Art Assets/monsters/water2.bmp"),
+];
+
+/// Top left corners of every frame of a sheet.
+fn frames(bmp: &Bmp<'_, BinaryColor>) -> impl Iterator<Item = (u32, u32)> {
+    let count = bmp.size().component_div(SPRITE);
+    (0..count.height).flat_map(move |y| (0..count.width).map(move |x| (x, y)))
+}
+
+/// Times drawing every frame of every sheet once, per frame drawn.
+fn time(name: &str, mut draw: impl FnMut(&mut Framebuffer, usize, (u32, u32))) -> Duration {
+    let bmps: Vec<_> = SHEETS
+        .iter()
+        .map(|data| Bmp::<BinaryColor>::from_slice(data).unwrap())
+        .collect();
+    let mut fb = Framebuffer::new();
+    let mut count = 0;
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for (sheet, bmp) in bmps.iter().enumerate() {
+            for frame in frames(bmp) {
+                draw(black_box(&mut fb), sheet, frame);
+                count += 1;
+            }
+        }
+    }
+    let per_frame = start.elapsed() / count;
+    println!("  {name:<32} {per_frame:>10.2?}");
+    per_frame
+}
+
+fn compare(name: &str, options: SpriteOptions) {
+    println!("{name}");
+    let pos = Point::new(40, 50);
+    let bmps: Vec<_> = SHEETS
+        .iter()
+        .map(|data| Bmp::<BinaryColor>::from_slice(data).unwrap())
+        .collect();
+    let sheets: Vec<_> = SHEETS
+        .iter()
+        .map(|data| SpriteSheet::new(data, SPRITE).unwrap())
+        .collect();
+
+    let scan = time("whole sheet scan", |fb, sheet, (x, y)| {
+        // only untransformed, the other options only add to this
+        let frame = Rectangle::new(Point::new((x * 64) as i32, (y * 64) as i32), SPRITE);
+        let pixels = bmps[sheet]
+            .pixels()
+            .filter(|Pixel(p, _)| frame.contains(*p))
+            .map(|Pixel(p, color)| Pixel(p - frame.top_left + pos, color));
+        fb.draw_iter(pixels).unwrap();
+    });
+    let pixels = time("pixel by pixel from the BMP", |fb, sheet, (x, y)| {
+        let offset = Point::new((x * 64) as i32, (y * 64) as i32);
+        let area = Rectangle::new(pos, SPRITE);
+        let colors = area
+            .points()
+            .map(|p| bmps[sheet].pixel(p - pos + offset).unwrap());
+        fb.fill_contiguous(&area, colors).unwrap();
+    });
+    let sheet = time("Sprite::draw", |fb, sheet, (x, y)| {
+        let sprite = sheets[sheet].sprite(x, y).unwrap();
+        sprite.draw(fb, pos, options).unwrap();
+    });
+    println!(
+        "  {:.0}x faster than the scan, {:.1}x faster than pixel by pixel\n",
+        scan.as_secs_f64() / sheet.as_secs_f64(),
+        pixels.as_secs_f64() / sheet.as_secs_f64(),
+    );
+}
+
+fn main() {
+    compare("plain", SpriteOptions::default());
+    compare(
+        "flip_x",
+        SpriteOptions {
+            flip_x: true,
+            ..SpriteOptions::default()
+        },
+    );
+    compare(
+        "R90",
+        SpriteOptions {
+            rotation: Rotation::R90,
+            ..SpriteOptions::default()
+        },
+    );
+    compare(
+        "scale 2",
+        SpriteOptions {
+            scale: 2,
+            ..SpriteOptions::default()
+        },
+    );
+    compare(
+        "TransparentOff",
+        SpriteOptions {
+            blend: BlendMode::TransparentOff,
+            ..SpriteOptions::default()
+        },
+    );
+}
@@ -28,7 +28,7 @@ async fn main(spawner: Spawner) {
     kywy_battery_from!(p => battery);
     kywy_usb_from!(spawner, p);
 
-    display.initialize().await;
+    display.initialize().await.unwrap();
     display.enable();
 
     let text_style = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
@@ -65,7 +65,7 @@ async fn main(spawner: Spawner) {
 
         battery.draw_async(&mut display).await.unwrap();
 
-        display.write_display().await;
+        display.write_display().await.ok();
         Timer::after(Duration::from_secs(1)).await;
     }
 }
@@ -64,7 +64,7 @@ async fn main(spawner: Spawner) {
     kywy_spi_from!(p => spi_bus);
     kywy_display_from!(spi_bus, p => display);
     kywy_usb_from!(spawner, p);
-    display.initialize().await;
+    display.initialize().await.unwrap();
     display.enable();
 
     kywy_button_async_from!(&spawner, p => buttons);
@@ -77,14 +77,14 @@ async fn main(spawner: Spawner) {
     let pos = Point::new((SCREEN_WIDTH - width) / 2, (SCREEN_HEIGHT - height) / 2);
     let image = Image::new(&bmp, pos);
     image.draw(&mut display).unwrap();
-    display.write_display().await;
+    display.write_display().await.ok();
     Timer::after(Duration::from_millis(200)).await;
     buttons.clear();
     let _: ButtonEvent = buttons.receive().await;
 
     loop {
         run_bricks(&mut display, &mut receiver).await;
-        display.write_display().await;
+        display.write_display().await.ok();
         wait_for_button(&mut receiver).await;
     }
 }
@@ -186,14 +186,14 @@ async fn run_bricks<SPI: SpiDevice>(
 
         if score == total_bricks {
             draw_message(display, "YOU WIN!");
-            display.write_display().await;
+            display.write_display().await.ok();
             Timer::after_secs(2).await;
             return;
         }
 
         if ball.pos.y >= SCREEN_HEIGHT {
             draw_message(display, "GAME OVER");
-            display.write_display().await;
+            display.write_display().await.ok();
             Timer::after_secs(2).await;
             return;
         }
@@ -203,7 +203,7 @@ async fn run_bricks<SPI: SpiDevice>(
         draw_paddle(display, &paddle);
         draw_bricks(display, &bricks);
         draw_score(display, score);
-        display.write_display().await;
+        display.write_display().await.ok();
 
         if tick_delay_ms > 10 {
             tick_delay_ms -= 1;
@@ -38,11 +38,11 @@ async fn main(spawner: Spawner) {
     kywy_display_from!(spi_bus, p => display);
     kywy_usb_from!(spawner, p);
     let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
-    display.initialize().await;
+    display.initialize().await.unwrap();
     display.enable();
     display.clear_buffer(BinaryColor::On);
     write_message(&mut display, "Button Test Started", style);
-    display.write_display().await;
+    display.write_display().await.ok();
 
     // Initialize buttons
     info!("Initializing buttons");
@@ -51,7 +51,7 @@ async fn main(spawner: Spawner) {
 
     display.clear(BinaryColor::Off).ok();
     write_message(&mut display, "Buttons Initialized", style);
-    display.write_display().await;
+    display.write_display().await.ok();
 
     loop {
         let event: ButtonEvent = button_channel.receive().await;
@@ -75,7 +75,7 @@ async fn main(spawner: Spawner) {
 
         info!("{}", msg);
         write_message(&mut display, msg, style);
-        display.write_display().await;
+        display.write_display().await.ok();
 
         Timer::after(Duration::from_millis(100)).await;
     }
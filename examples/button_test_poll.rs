@@ -39,12 +39,12 @@ async fn main(spawner: Spawner) {
     kywy_usb_from!(spawner, p);
     let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
 
-    display.initialize().await;
+    display.initialize().await.unwrap();
     display.enable();
 
     display.clear_buffer(BinaryColor::On);
     write_message(&mut display, "Polling Buttons", style);
-    display.write_display().await;
+    display.write_display().await.ok();
 
     // Initialize buttons
     kywy_button_poll_from!(p => poller);
@@ -76,7 +76,7 @@ async fn main(spawner: Spawner) {
             let _ = Text::new(state, Point::new(70, y), style).draw(&mut display);
         }
 
-        display.write_display().await;
+        display.write_display().await.ok();
         Timer::after(Duration::from_millis(100)).await;
     }
 }
@@ -5,6 +5,7 @@
 //! examples/display_image.rs
 //! Bitmap Graphic Demo for Kywy display
 //! Make sure BMP is converted to monochrome useing magick "examples/Art Assets/image_file" -resize 144x168 -monochrome -depth 1 BMP3:"examples/Art Assets/filename.bmp"
+//! Grayscale or color BMPs can be drawn as they are through `kywy::dither::Dithered`
 
 #![no_std]
 #![no_main]
@@ -28,7 +29,7 @@ async fn main(spawner: embassy_executor::Spawner) {
     kywy_display_from!(spi_bus, p => display);
     kywy_usb_from!(spawner, p);
 
-    display.initialize().await;
+    display.initialize().await.unwrap();
     display.enable();
 
     display.clear_buffer(BinaryColor::On);
@@ -39,10 +40,10 @@ async fn main(spawner: embassy_executor::Spawner) {
     let image = Image::new(&bmp, Point::zero());
     image.draw(&mut display).unwrap();
 
-    display.write_display().await;
+    display.write_display().await.ok();
 
     loop {
-        display.toggle_vcom().await;
+        display.toggle_vcom().await.ok();
         Timer::after(Duration::from_secs(1)).await;
     }
 }
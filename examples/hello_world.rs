@@ -31,12 +31,12 @@ async fn main(spawner: embassy_executor::Spawner) {
 
     // Black Screen
     display.clear_buffer(BinaryColor::Off); // Clear buffer to black
-    display.write_display().await;
+    display.write_display().await.ok();
     Timer::after(Duration::from_millis(1000)).await;
 
     // White Screen
     display.clear_buffer(BinaryColor::On); // Clear buffer to white
-    display.write_display().await;
+    display.write_display().await.ok();
     Timer::after(Duration::from_millis(1000)).await;
 
     // Checkerboard pattern
@@ -54,7 +54,7 @@ async fn main(spawner: embassy_executor::Spawner) {
             );
         }
     }
-    display.write_display().await;
+    display.write_display().await.ok();
     Timer::after(Duration::from_millis(1000)).await;
 
     // Write Hello, Rust! to display using the embedded-graphics crate
@@ -65,11 +65,11 @@ async fn main(spawner: embassy_executor::Spawner) {
         .draw(&mut display)
         .unwrap();
 
-    display.write_display().await;
+    display.write_display().await.ok();
 
     // Main loop - toggle VCOM every second to prevent DC bias
     loop {
-        display.toggle_vcom().await;
+        display.toggle_vcom().await.ok();
         Timer::after(Duration::from_secs(1)).await;
     }
 }
@@ -13,7 +13,7 @@ use defmt::*;
 use defmt_rtt as _;
 use panic_probe as _;
 
-use kywy::button_async::{ButtonEvent, ButtonId, ButtonState};
+use kywy::button_async::{ButtonId, ButtonState};
 use kywy::{kywy_button_async_from, kywy_display_from, kywy_spi_from, kywy_usb_from}; // Import the macros
 
 use embassy_executor::Spawner;
@@ -42,7 +42,7 @@ async fn main(spawner: Spawner) {
     kywy_spi_from!(p => spi_bus);
     kywy_display_from!(spi_bus, p => display);
     kywy_usb_from!(spawner, p);
-    display.initialize().await;
+    display.initialize().await.unwrap();
     display.enable();
     display.clear_buffer(BinaryColor::On);
     let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
@@ -65,10 +65,13 @@ async fn main(spawner: Spawner) {
     display.clear_buffer(BinaryColor::On); // clear perevious message
     menu.update(&display); // update the menu with the init state
     menu.draw(&mut display).unwrap(); // write to display buffer
-    display.write_display().await; // update display
+    display.write_display().await.ok(); // update display
 
     loop {
-        let event: ButtonEvent = button_channel.receive().await; // wait for button event
+        // wait for button event
+        let Ok(event) = display.wait_with_vcom(button_channel.receive()).await else {
+            continue;
+        };
         let interaction = match (event.id, event.state) {
             // match button event to desired input and map to a menu interaction
             (ButtonId::DUp, ButtonState::Pressed) => {
@@ -85,7 +88,7 @@ async fn main(spawner: Spawner) {
             menu.interact(i); // sends the interaction to the menu system
             menu.update(&display); // update the menu with the new state
             menu.draw(&mut display).unwrap(); // draws the menu to display buffer
-            display.write_display().await; // update display
+            display.write_display().await.ok(); // update display
         }
     }
 }
@@ -15,7 +15,7 @@ use panic_probe as _;
 use core::cell::Cell;
 use itoa::Buffer;
 
-use kywy::button_async::{ButtonEvent, ButtonId, ButtonState};
+use kywy::button_async::{ButtonId, ButtonState};
 use kywy::{kywy_button_async_from, kywy_display_from, kywy_spi_from, kywy_usb_from};
 
 use embassy_executor::Spawner;
@@ -263,17 +263,17 @@ async fn main(spawner: Spawner) {
     kywy_usb_from!(spawner, p);
 
     let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
-    display.initialize().await;
+    display.initialize().await.unwrap();
     display.enable();
 
     static IMAGE_DATA: &[u8] = include_bytes!("../examples/Art Assets/SnakeSplash.bmp"); // Change this path to use a different image
     let bmp = Bmp::from_slice(IMAGE_DATA).unwrap();
     let image = Image::new(&bmp, Point::zero());
     image.draw(&mut display).unwrap();
-    display.write_display().await;
+    display.write_display().await.ok();
     Timer::after(Duration::from_millis(200)).await;
     kywy_button_async_from!(&spawner, p => button_channel);
-    let _: ButtonEvent = button_channel.receive().await; // Wait for any button press
+    let _ = display.wait_with_vcom(button_channel.receive()).await; // Wait for any button press
 
     let held = ButtonHeldState::default();
 
@@ -295,7 +295,7 @@ async fn main(spawner: Spawner) {
 
             let alive = game.update();
             game.render(&mut display);
-            display.write_display().await;
+            display.write_display().await.ok();
 
             // Reset "just pressed" flags so they only last one frame
             held.clear_just_pressed();
@@ -308,12 +308,12 @@ async fn main(spawner: Spawner) {
         }
 
         game.render_game_over(&mut display, style);
-        display.write_display().await;
+        display.write_display().await.ok();
         Timer::after(Duration::from_millis(500)).await;
 
         loop {
-            let ev = button_channel.receive().await;
-            if ev.state == ButtonState::Pressed {
+            let ev = display.wait_with_vcom(button_channel.receive()).await;
+            if ev.is_ok_and(|ev| ev.state == ButtonState::Pressed) {
                 break;
             }
         }
@@ -3,7 +3,8 @@
 
 //! This example demonstrates the use of the `Sprite` struct from the `kywy` crate.
 //! It creates a sprite from a sprite sheet and animates it on a display.
-//! This only uses sprites.rs and no additional engine components to create animations with button events and move a sprite around with the dpad.
+//! This uses sprites.rs and the renderer from the engine to create animations with button events and move a sprite around with the dpad.
+//! Games with a build script can generate these frame lists from `Art Assets/monsters/electric.toml` with `kywy::import` instead.
 
 #![no_std]
 #![no_main]
@@ -11,16 +12,37 @@
 use defmt::*;
 use defmt_rtt as _;
 use embassy_executor::Spawner;
-use embassy_time::{Duration, Timer};
-use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+use embassy_time::{Duration, Instant, Timer};
+use embedded_graphics::prelude::*;
 use heapless::Vec;
 use kywy::{
     button_async::{ButtonId, ButtonState},
-    engine::sprite::{Animation, Rotation, SpriteInstance, SpriteOptions, SpriteSheet},
+    engine::renderer::Renderer,
+    engine::sprite::{
+        Animation, AnimationEvents, BlendMode, PlayMode, Rotation, SpriteInstance, SpriteOptions,
+        SpriteSheet, StateTransition,
+    },
     kywy_button_async_from, kywy_display_from, kywy_spi_from, kywy_usb_from,
 };
 use panic_probe as _;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MonsterState {
+    Idle,
+    LeftTrigger,
+    RightTrigger,
+}
+
+// triggers can start from idle and play to the end before returning to idle
+const TRANSITIONS: &[StateTransition<MonsterState>] = &[
+    StateTransition::interrupt(MonsterState::Idle, MonsterState::LeftTrigger),
+    StateTransition::interrupt(MonsterState::Idle, MonsterState::RightTrigger),
+    StateTransition::on_any_finish(MonsterState::Idle),
+];
+
+// raised on the last frame of either trigger animation
+const STRIKE: AnimationEvents = AnimationEvents::event(0);
+
 #[embassy_executor::main]
 async fn main(spawner: Spawner) {
     info!("Sprite D-Pad control test");
@@ -31,7 +53,7 @@ async fn main(spawner: Spawner) {
     kywy_button_async_from!(&spawner, p => button_channel);
     kywy_usb_from!(spawner, p);
 
-    display.initialize().await;
+    display.initialize().await.unwrap();
     display.enable();
 
     static SPRITE_DATA: &[u8] = include_bytes!("../examples/Art Assets/monsters/electric.bmp");
@@ -40,42 +62,75 @@ async fn main(spawner: Spawner) {
     let idle: &[(u32, u32)] = &[(0, 0), (1, 0), (2, 0)];
     let left_trigger: &[(u32, u32)] = &[(0, 1), (1, 1), (2, 1)];
     let right_trigger: &[(u32, u32)] = &[(0, 2), (1, 2), (2, 2)];
-
-    let mut animations: Vec<_, 4> = Vec::new();
-    animations.push(Animation::new(&sheet, idle, true)).unwrap();
-    animations
-        .push(Animation::new(&sheet, left_trigger, false))
+    // hold the last frame of the attack a little longer
+    let trigger_durations: &[Duration] = &[
+        Duration::from_millis(80),
+        Duration::from_millis(80),
+        Duration::from_millis(250),
+    ];
+    let trigger_events = &[AnimationEvents::NONE, AnimationEvents::NONE, STRIKE];
+
+    let mut states: Vec<_, 4> = Vec::new();
+    states
+        .push((
+            MonsterState::Idle,
+            Animation::new(&sheet, idle, true)
+                .with_mode(PlayMode::PingPong)
+                .with_frame_duration(Duration::from_millis(150)),
+        ))
         .unwrap();
-    animations
-        .push(Animation::new(&sheet, right_trigger, false))
+    states
+        .push((
+            MonsterState::LeftTrigger,
+            Animation::new(&sheet, left_trigger, false)
+                .with_durations(trigger_durations)
+                .with_events(trigger_events),
+        ))
+        .unwrap();
+    states
+        .push((
+            MonsterState::RightTrigger,
+            Animation::new(&sheet, right_trigger, false)
+                .with_durations(trigger_durations)
+                .with_events(trigger_events),
+        ))
         .unwrap();
 
-    let mut sprite = SpriteInstance::new(animations, Point::new(40, 40));
+    let mut sprite =
+        SpriteInstance::with_states(states, Point::new(40, 40)).with_transitions(TRANSITIONS);
     let mut sprite_options = SpriteOptions {
         flip_x: false,
         flip_y: false,
         rotation: Rotation::None,
+        blend: BlendMode::TransparentOn, // white pixels let the background show through
+        ..Default::default()
     };
 
     let mut velocity = Point::zero();
+    let mut last_update = Instant::now();
+    let mut renderer: Renderer<4> = Renderer::new();
 
     loop {
         // Capture all button events
         while let Ok(event) = button_channel.try_receive() {
             match event.state {
                 ButtonState::Pressed => match event.id {
-                    ButtonId::Left => sprite.trigger(1),
-                    ButtonId::Right => sprite.trigger(2),
+                    ButtonId::Left => {
+                        sprite.request(MonsterState::LeftTrigger);
+                    }
+                    ButtonId::Right => {
+                        sprite.request(MonsterState::RightTrigger);
+                    }
                     ButtonId::DLeft => {
-                        velocity.x = -5;
+                        velocity.x = -2;
                         sprite_options.flip_x = true;
                     }
                     ButtonId::DRight => {
-                        velocity.x = 5;
+                        velocity.x = 2;
                         sprite_options.flip_x = false;
                     }
-                    ButtonId::DUp => velocity.y = -5,
-                    ButtonId::DDown => velocity.y = 5,
+                    ButtonId::DUp => velocity.y = -2,
+                    ButtonId::DDown => velocity.y = 2,
                     _ => {}
                 },
                 ButtonState::Released => match event.id {
@@ -89,17 +144,19 @@ async fn main(spawner: Spawner) {
         // Move sprite by velocity
         sprite.move_by(velocity.x, velocity.y);
 
-        // Draw current frame
-        let frame = sprite.current().current_frame_sprite().unwrap();
-        display.clear(BinaryColor::On).unwrap();
-        frame
-            .draw(&mut display, sprite.position, sprite_options)
-            .unwrap();
-        display.write_display().await;
+        // Draw current frame, only the area the sprite left or moved into is redrawn
+        renderer.instance(0, &sprite, sprite_options).ok();
+        if !renderer.render_dirty(&mut display).unwrap().is_empty() {
+            display.write_display().await.ok();
+        }
 
-        // Update animation
-        sprite.update(0);
+        // Update animation by the time this frame took, whatever the loop rate
+        let now = Instant::now();
+        if sprite.update(now - last_update).contains(STRIKE) {
+            info!("Strike!");
+        }
+        last_update = now;
 
-        Timer::after(Duration::from_millis(100)).await;
+        Timer::after(Duration::from_millis(30)).await;
     }
 }
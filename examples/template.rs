@@ -47,7 +47,7 @@ async fn main(spawner: Spawner) {
     //kywy_button_poll_from!(p => poller); // button poller function, gets current state of the buttons
 
     // initialize display and turn it on
-    display.initialize().await;
+    display.initialize().await.unwrap();
     display.enable();
 
     // Add your code here
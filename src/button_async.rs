@@ -4,15 +4,22 @@
 
 //! src/buttons.rs
 //! Button event system using a shared Channel for press/release detection.
+//! The event types also build on a host so simulated input can use them.
 
+#[cfg(target_os = "none")]
 use embassy_executor::Spawner;
+#[cfg(target_os = "none")]
 use embassy_rp::PeripheralRef;
+#[cfg(target_os = "none")]
 use embassy_rp::gpio::{Input, Level, Pull};
+#[cfg(target_os = "none")]
 use embassy_rp::peripherals::*;
+#[cfg(target_os = "none")]
 use embassy_sync::blocking_mutex::raw::ThreadModeRawMutex;
+#[cfg(target_os = "none")]
 use embassy_sync::channel::Channel;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ButtonId {
     Left,
     Right,
@@ -35,11 +42,14 @@ pub struct ButtonEvent {
     pub state: ButtonState,
 }
 
+#[cfg(target_os = "none")]
 const BUTTON_CHANNEL_CAPACITY: usize = 16;
 
+#[cfg(target_os = "none")]
 static BUTTON_CHANNEL: Channel<ThreadModeRawMutex, ButtonEvent, BUTTON_CHANNEL_CAPACITY> =
     Channel::new();
 
+#[cfg(target_os = "none")]
 pub struct ButtonPins {
     pub left: PeripheralRef<'static, PIN_12>,
     pub right: PeripheralRef<'static, PIN_2>,
@@ -50,6 +60,7 @@ pub struct ButtonPins {
     pub dcenter: PeripheralRef<'static, PIN_8>,
 }
 
+#[cfg(target_os = "none")]
 pub fn init(
     spawner: &Spawner,
     pins: ButtonPins,
@@ -65,6 +76,7 @@ pub fn init(
     &BUTTON_CHANNEL
 }
 
+#[cfg(target_os = "none")]
 fn spawn_button<P: embassy_rp::gpio::Pin + 'static>(
     spawner: &Spawner,
     pin: PeripheralRef<'static, P>,
@@ -77,6 +89,7 @@ fn spawn_button<P: embassy_rp::gpio::Pin + 'static>(
     }
 }
 
+#[cfg(target_os = "none")]
 #[embassy_executor::task(pool_size = 7)] // spawns 7 tasks, one for each button
 async fn button_task(mut pin: Input<'static>, id: ButtonId) {
     loop {
@@ -5,22 +5,68 @@
 //! Kywy Display Driver
 //! Compatable with embedded-graphics DrawTarget
 //! for use with LS013B7DH05
+//!
+//! Drawing happens in an owned [`Framebuffer`], the driver only sends the lines
+//! that changed since the last flush, batched into multi-line write commands.
+//!
+//! The panel needs VCOM toggled regularly even when the image is static. Use
+//! [`KywyDisplay::wait_with_vcom`] while idling on input, or [`maintain_vcom`]
+//! from a task when the display is shared behind a mutex.
+//!
+//! For double buffering hand the display to [`display_task`] and present frames
+//! through a [`DoubleBuffer`] instead of calling `write_display`.
+//!
+//! Bus errors are returned as [`DisplayError`] rather than panicking. Lines that
+//! failed to send stay dirty, so the next `write_display` retries them.
 
-use core::ops::Not;
+use crate::double_buffer::DoubleBuffer;
+use crate::framebuffer::{
+    BYTES_PER_LINE, DisplayRotation, Framebuffer, HEIGHT, LineMask, all_lines,
+};
+use core::convert::Infallible;
+use core::future::Future;
+use core::ops::{Not, Range};
+use core::pin::pin;
+use embassy_embedded_hal::shared_bus::asynch::spi::SpiDeviceWithConfig;
+use embassy_futures::select::{Either, select};
 use embassy_rp::gpio::Output;
+use embassy_rp::peripherals::SPI0;
+use embassy_rp::spi::{Async, Spi};
+use embassy_sync::blocking_mutex::raw::{CriticalSectionRawMutex, RawMutex};
+use embassy_sync::mutex::Mutex;
+use embassy_time::{Duration, Instant, Timer};
 use embedded_graphics::{
     Pixel,
     draw_target::DrawTarget,
+    geometry::Point,
+    image::GetPixel,
     pixelcolor::BinaryColor,
     prelude::{OriginDimensions, Size},
+    primitives::Rectangle,
 };
 use embedded_hal_async::spi::SpiDevice;
+use inverted_pin::InvertedPin;
+
+const LINE_PACKET_SIZE: usize = 1 + BYTES_PER_LINE + 1; // address, data, dummy byte
+const LINES_PER_TRANSFER: usize = 16; // bounds the packet buffer, a full frame would need ~3.3KB of RAM
+const PACKET_SIZE: usize = 1 + LINES_PER_TRANSFER * LINE_PACKET_SIZE + 1; // command, lines, trailing dummy byte
+const DEFAULT_VCOM_PERIOD: Duration = Duration::from_secs(1);
+const RETRY_DELAY: Duration = Duration::from_millis(20); // before resending lines that failed without a new frame
+
+/// The SPI device created by `kywy_display_from!`
+pub type KywyDisplaySpi = SpiDeviceWithConfig<
+    'static,
+    CriticalSectionRawMutex,
+    Spi<'static, SPI0, Async>,
+    InvertedPin<Output<'static>>,
+>;
 
-const WIDTH: usize = 144;
-const HEIGHT: usize = 168;
-const TOTAL_BUFFER_SIZE: usize = WIDTH * HEIGHT / 8;
-const BYTES_PER_LINE: usize = WIDTH / 8;
-const LINE_PACKET_SIZE: usize = 2 + BYTES_PER_LINE + 2;
+/// Errors returned when talking to the panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayError<E> {
+    /// The SPI transfer failed
+    Spi(E),
+}
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum Vcom {
@@ -48,10 +94,12 @@ enum Command {
 pub struct KywyDisplay<'a, SPI> {
     spi: SPI,
     disp: Output<'a>,
-    buffer: [u8; TOTAL_BUFFER_SIZE],
-    line_buf: [u8; LINE_PACKET_SIZE],
+    framebuffer: Framebuffer,
+    packet_buf: [u8; PACKET_SIZE],
     vcom: Vcom,
     auto_vcom: bool,
+    vcom_period: Duration,
+    last_vcom: Instant,
 }
 
 impl<'a, SPI> KywyDisplay<'a, SPI>
@@ -62,18 +110,21 @@ where
         Self {
             spi,
             disp,
-            buffer: [0x00; TOTAL_BUFFER_SIZE],
-            line_buf: [0x00; LINE_PACKET_SIZE],
+            framebuffer: Framebuffer::new(), // starts fully dirty, panel contents are unknown until the first flush
+            packet_buf: [0x00; PACKET_SIZE],
             vcom: Vcom::Hi,
             auto_vcom: true, //defaults to toggling vcom every display update
+            vcom_period: DEFAULT_VCOM_PERIOD,
+            last_vcom: Instant::now(),
         }
     }
 
-    pub async fn initialize(&mut self) {
+    pub async fn initialize(&mut self) -> Result<(), DisplayError<SPI::Error>> {
         self.disable();
         self.vcom = Vcom::Hi;
-        self.clear_display().await;
+        self.clear_display().await?;
         self.enable();
+        Ok(())
     }
 
     pub fn enable(&mut self) {
@@ -84,35 +135,123 @@ where
         self.disp.set_low();
     }
 
-    pub async fn write_spi(&mut self, data: &[u8]) {
-        self.spi.transfer(&mut [], data).await.unwrap();
+    pub async fn write_spi(&mut self, data: &[u8]) -> Result<(), DisplayError<SPI::Error>> {
+        self.spi
+            .transfer(&mut [], data)
+            .await
+            .map_err(DisplayError::Spi)
     }
 
-    pub async fn write_display(&mut self) {
-        if self.auto_vcom {
-            self.vcom = !self.vcom;
+    /// Sends every line that changed since the last flush.
+    /// If nothing changed only VCOM is toggled (when auto VCOM is enabled).
+    pub async fn write_display(&mut self) -> Result<(), DisplayError<SPI::Error>> {
+        if !self.framebuffer.is_dirty() {
+            if self.auto_vcom {
+                self.toggle_vcom().await?;
+            }
+            return Ok(());
         }
 
-        for line in 0..HEIGHT {
-            self.line_buf[0] = Command::WriteLine as u8 | self.vcom as u8 | 0x80;
-            self.line_buf[1] = (line as u8 + 1).reverse_bits();
+        let lines = self.framebuffer.dirty_lines();
+        self.send_lines(lines).await
+    }
+
+    /// Sends the given lines to the panel whether or not they changed.
+    pub async fn write_lines(
+        &mut self,
+        lines: Range<usize>,
+    ) -> Result<(), DisplayError<SPI::Error>> {
+        let mut mask = LineMask::ZERO;
+        // a reversed range is empty rather than a panic
+        let end = lines.end.min(HEIGHT);
+        let start = lines.start.min(end);
+        mask[start..end].fill(true);
+        self.send_lines(mask).await
+    }
+
+    /// Sends the whole buffer to the panel.
+    pub async fn force_full_refresh(&mut self) -> Result<(), DisplayError<SPI::Error>> {
+        self.send_lines(all_lines()).await
+    }
+
+    async fn send_lines(&mut self, lines: LineMask) -> Result<(), DisplayError<SPI::Error>> {
+        if lines.not_any() {
+            return Ok(());
+        }
+        if self.auto_vcom {
+            self.flip_vcom();
+        }
+        transfer_lines(
+            &mut self.spi,
+            &mut self.packet_buf,
+            self.vcom,
+            &mut self.framebuffer,
+            lines,
+        )
+        .await
+    }
 
-            let buf_start = line * BYTES_PER_LINE;
-            for i in 0..BYTES_PER_LINE {
-                self.line_buf[2 + i] = self.buffer[buf_start + i].reverse_bits();
+    /// Sends every changed line of a buffer other than the display's own, like
+    /// `write_display`. Used by the double buffered display task.
+    pub async fn write_framebuffer(
+        &mut self,
+        framebuffer: &mut Framebuffer,
+    ) -> Result<(), DisplayError<SPI::Error>> {
+        if !framebuffer.is_dirty() {
+            if self.auto_vcom {
+                self.toggle_vcom().await?;
             }
+            return Ok(());
+        }
+        if self.auto_vcom {
+            self.flip_vcom();
+        }
 
-            self.line_buf[2 + BYTES_PER_LINE] = 0x00;
-            self.line_buf[3 + BYTES_PER_LINE] = 0x00;
+        let lines = framebuffer.dirty_lines();
+        transfer_lines(
+            &mut self.spi,
+            &mut self.packet_buf,
+            self.vcom,
+            framebuffer,
+            lines,
+        )
+        .await
+    }
 
-            self.spi.write(&self.line_buf).await.unwrap();
+    /// Streams frames presented to `frames` until the end of time, keeping VCOM
+    /// toggled in between. The game keeps drawing while a frame is sent.
+    pub async fn run_double_buffered<M: RawMutex>(&mut self, frames: &DoubleBuffer<M>) -> ! {
+        let mut retry = false;
+        loop {
+            let frame = async {
+                if !retry {
+                    return frames.next_frame().await;
+                }
+                // lines that failed to send stay dirty, don't wait for a new frame to send
+                // them or anyone waiting for the frame to complete waits forever
+                match select(frames.next_frame(), Timer::after(RETRY_DELAY)).await {
+                    Either::First(front) => front,
+                    Either::Second(()) => frames.front().await,
+                }
+            };
+            // a failed VCOM toggle is retried on the next period
+            let Ok(mut front) = self.wait_with_vcom(frame).await else {
+                continue;
+            };
+            retry = self.write_framebuffer(&mut front).await.is_err();
+            frames.finish_frame();
         }
     }
 
-    pub async fn toggle_vcom(&mut self) {
+    fn flip_vcom(&mut self) {
         self.vcom = !self.vcom;
+        self.last_vcom = Instant::now();
+    }
+
+    pub async fn toggle_vcom(&mut self) -> Result<(), DisplayError<SPI::Error>> {
+        self.flip_vcom();
         self.write_spi(&[Command::Nop as u8 | self.vcom as u8, 0x00])
-            .await;
+            .await
     }
 
     pub fn set_auto_vcom(&mut self, enable: bool) {
@@ -123,44 +262,172 @@ where
         self.auto_vcom
     }
 
-    pub async fn clear_display(&mut self) {
+    /// Sets how often VCOM is toggled while the screen is idle, defaults to once a second.
+    pub fn set_vcom_period(&mut self, period: Duration) {
+        self.vcom_period = period;
+    }
+
+    pub fn vcom_period(&self) -> Duration {
+        self.vcom_period
+    }
+
+    /// Toggles VCOM if it has not been toggled within the VCOM period.
+    pub async fn service_vcom(&mut self) -> Result<(), DisplayError<SPI::Error>> {
+        if Instant::now() >= self.last_vcom + self.vcom_period {
+            self.toggle_vcom().await?;
+        }
+        Ok(())
+    }
+
+    /// Waits for `fut` to complete while keeping VCOM toggled at the VCOM period.
+    /// Use this instead of a bare `.await` when a static screen waits on input.
+    /// If a toggle fails `fut` is dropped and the error returned.
+    pub async fn wait_with_vcom<F: Future>(
+        &mut self,
+        fut: F,
+    ) -> Result<F::Output, DisplayError<SPI::Error>> {
+        let mut fut = pin!(fut);
+        loop {
+            let deadline = self.last_vcom + self.vcom_period;
+            match select(fut.as_mut(), Timer::at(deadline)).await {
+                Either::First(output) => return Ok(output),
+                Either::Second(()) => self.toggle_vcom().await?,
+            }
+        }
+    }
+
+    pub async fn clear_display(&mut self) -> Result<(), DisplayError<SPI::Error>> {
         if self.auto_vcom {
-            self.vcom = !self.vcom;
+            self.flip_vcom();
         }
         self.write_spi(&[Command::ClearMemory as u8 | self.vcom as u8, 0x00])
-            .await;
+            .await?;
+        // the panel no longer matches the buffer
+        self.framebuffer.mark_all_dirty();
+        Ok(())
     }
 
     pub fn clear_buffer(&mut self, color: BinaryColor) {
-        let fill_value = if color.is_on() { 0xFF } else { 0x00 };
-        self.buffer.fill(fill_value);
+        self.framebuffer.fill(color);
+    }
+
+    /// Returns true if any line changed since the last flush.
+    pub fn is_dirty(&self) -> bool {
+        self.framebuffer.is_dirty()
+    }
+
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.framebuffer
+    }
+
+    pub fn framebuffer_mut(&mut self) -> &mut Framebuffer {
+        &mut self.framebuffer
     }
 
     pub fn set_pixel(&mut self, x: usize, y: usize, color: BinaryColor) {
-        if x >= WIDTH || y >= HEIGHT {
-            return;
-        }
+        self.framebuffer.set_pixel(x, y, color);
+    }
+
+    /// Rotates all following drawing, `Rotate90`/`Rotate270` give a 168x144 landscape screen.
+    pub fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.framebuffer.set_rotation(rotation);
+    }
 
-        let index = (y * BYTES_PER_LINE) + (x / 8);
-        let bit = x % 8;
+    pub fn rotation(&self) -> DisplayRotation {
+        self.framebuffer.rotation()
+    }
 
-        if color.is_on() {
-            self.buffer[index] |= 1 << bit;
-        } else {
-            self.buffer[index] &= !(1 << bit);
-        }
+    /// Mirrors all following drawing, applied before the rotation.
+    pub fn set_mirror(&mut self, horizontal: bool, vertical: bool) {
+        self.framebuffer.set_mirror(horizontal, vertical);
     }
+
     pub fn height(&self) -> usize {
-        HEIGHT
+        self.framebuffer.height()
     }
     pub fn width(&self) -> usize {
-        WIDTH
+        self.framebuffer.width()
+    }
+}
+
+/// Sends `lines` of `framebuffer` in as few transfers as the packet buffer allows.
+/// Lines are only marked clean once every transfer succeeded.
+async fn transfer_lines<SPI: SpiDevice>(
+    spi: &mut SPI,
+    packet_buf: &mut [u8; PACKET_SIZE],
+    vcom: Vcom,
+    framebuffer: &mut Framebuffer,
+    lines: LineMask,
+) -> Result<(), DisplayError<SPI::Error>> {
+    let mut pending = lines[..HEIGHT].iter_ones().peekable();
+    while pending.peek().is_some() {
+        packet_buf[0] = Command::WriteLine as u8 | vcom as u8;
+        let mut len = 1;
+
+        for line in pending.by_ref().take(LINES_PER_TRANSFER) {
+            packet_buf[len] = (line as u8 + 1).reverse_bits();
+
+            let data = &mut packet_buf[len + 1..len + 1 + BYTES_PER_LINE];
+            for (dst, src) in data.iter_mut().zip(framebuffer.line(line)) {
+                *dst = src.reverse_bits();
+            }
+
+            packet_buf[len + 1 + BYTES_PER_LINE] = 0x00;
+            len += LINE_PACKET_SIZE;
+        }
+
+        packet_buf[len] = 0x00;
+        len += 1;
+
+        spi.write(&packet_buf[..len])
+            .await
+            .map_err(DisplayError::Spi)?;
     }
+
+    framebuffer.clear_dirty(lines);
+    Ok(())
+}
+
+/// Keeps VCOM toggled on a display shared behind a mutex, run it from a task.
+/// Frame writes lock the same mutex, so the two never interleave on the bus.
+pub async fn maintain_vcom<M, SPI>(display: &Mutex<M, KywyDisplay<'_, SPI>>) -> !
+where
+    M: RawMutex,
+    SPI: SpiDevice,
+{
+    loop {
+        let deadline = {
+            let display = display.lock().await;
+            display.last_vcom + display.vcom_period
+        };
+        Timer::at(deadline).await;
+        // a frame write may have toggled VCOM in the meantime, a failed
+        // toggle is simply retried on the next period
+        display.lock().await.service_vcom().await.ok();
+    }
+}
+
+/// Display task for double buffering, draw into a back buffer and hand it to
+/// [`DoubleBuffer::present`].
+#[embassy_executor::task]
+pub async fn display_task(
+    mut display: KywyDisplay<'static, KywyDisplaySpi>,
+    frames: &'static DoubleBuffer<CriticalSectionRawMutex>,
+) {
+    display.run_double_buffered(frames).await
 }
 
 impl<SPI> OriginDimensions for KywyDisplay<'_, SPI> {
     fn size(&self) -> Size {
-        Size::new(WIDTH as u32, HEIGHT as u32)
+        self.framebuffer.size()
+    }
+}
+
+impl<SPI> GetPixel for KywyDisplay<'_, SPI> {
+    type Color = BinaryColor;
+
+    fn pixel(&self, p: Point) -> Option<Self::Color> {
+        self.framebuffer.pixel(p)
     }
 }
 
@@ -169,21 +436,29 @@ where
     SPI: SpiDevice,
 {
     type Color = BinaryColor;
-    type Error = ();
+    // drawing only touches the framebuffer, bus errors surface from `write_display`
+    type Error = Infallible;
 
     fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
     where
         I: IntoIterator<Item = Pixel<Self::Color>>,
     {
-        for Pixel(coord, color) in pixels {
-            if coord.x >= 0 && coord.y >= 0 {
-                let x = coord.x as usize;
-                let y = coord.y as usize;
-                if x < WIDTH && y < HEIGHT {
-                    self.set_pixel(x, y, color);
-                }
-            }
-        }
+        self.framebuffer.draw_iter(pixels)
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        self.framebuffer.fill_contiguous(area, colors)
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.framebuffer.fill_solid(area, color)
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.framebuffer.fill(color);
         Ok(())
     }
 }
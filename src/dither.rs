@@ -0,0 +1,210 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Grayscale and color drawing on the 1bpp display.
+//!
+//! [`Dithered`] wraps any `BinaryColor` draw target and accepts `Gray8`, `Rgb565`
+//! or any other color convertible to `Gray8`, so 8-bit BMPs can be drawn without
+//! converting them by hand first:
+//!
+//! ```ignore
+//! let bmp: Bmp<Gray8> = Bmp::from_slice(IMAGE_DATA).unwrap();
+//! let mut dithered = Dithered::new(&mut display, DitherMode::FloydSteinberg);
+//! Image::new(&bmp, Point::zero()).draw(&mut dithered).unwrap();
+//! ```
+
+use core::marker::PhantomData;
+use embedded_graphics::{
+    Pixel,
+    draw_target::DrawTarget,
+    geometry::Point,
+    pixelcolor::{BinaryColor, Gray8, GrayColor, PixelColor},
+    prelude::Dimensions,
+    primitives::{PointsIter, Rectangle},
+};
+
+// error diffusion keeps two rows of error terms, wider areas are thresholded past this
+const MAX_DIFFUSION_WIDTH: usize = 256;
+
+const BAYER_4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+const BAYER_8: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// How gray levels are reduced to black and white
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DitherMode {
+    /// Pixels brighter than or equal to the level are white
+    Threshold(u8),
+    /// Ordered dithering with a 4x4 Bayer matrix, 17 gray levels
+    #[default]
+    Bayer4,
+    /// Ordered dithering with an 8x8 Bayer matrix, 65 gray levels
+    Bayer8,
+    /// Floyd–Steinberg error diffusion. Needs whole rows, so pixels drawn one
+    /// by one through `draw_iter` use `Bayer8` instead.
+    FloydSteinberg,
+}
+
+impl DitherMode {
+    /// Converts a single pixel, the pattern is anchored to the screen origin so
+    /// neighbouring draws line up.
+    fn ordered(self, point: Point, luma: u8) -> BinaryColor {
+        let (x, y) = (
+            point.x.rem_euclid(8) as usize,
+            point.y.rem_euclid(8) as usize,
+        );
+        let threshold = match self {
+            DitherMode::Threshold(level) => return BinaryColor::from(luma >= level),
+            DitherMode::Bayer4 => BAYER_4[y % 4][x % 4] as u16 * 16 + 8,
+            DitherMode::Bayer8 | DitherMode::FloydSteinberg => BAYER_8[y][x] as u16 * 4 + 2,
+        };
+        BinaryColor::from(luma as u16 >= threshold)
+    }
+}
+
+/// Draw target adapter that dithers colors of type `C` onto a `BinaryColor` target.
+pub struct Dithered<'a, D, C = Gray8> {
+    target: &'a mut D,
+    mode: DitherMode,
+    color: PhantomData<C>,
+}
+
+impl<'a, D, C> Dithered<'a, D, C>
+where
+    D: DrawTarget<Color = BinaryColor>,
+    C: PixelColor + Into<Gray8>,
+{
+    pub fn new(target: &'a mut D, mode: DitherMode) -> Self {
+        Self {
+            target,
+            mode,
+            color: PhantomData,
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: DitherMode) {
+        self.mode = mode;
+    }
+
+    pub fn mode(&self) -> DitherMode {
+        self.mode
+    }
+}
+
+impl<D, C> Dimensions for Dithered<'_, D, C>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<D, C> DrawTarget for Dithered<'_, D, C>
+where
+    D: DrawTarget<Color = BinaryColor>,
+    C: PixelColor + Into<Gray8>,
+{
+    type Color = C;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let mode = self.mode;
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(p, c)| Pixel(p, mode.ordered(p, c.into().luma()))),
+        )
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let lumas = colors.into_iter().map(|c| c.into().luma());
+        match self.mode {
+            DitherMode::FloydSteinberg => self
+                .target
+                .fill_contiguous(area, FloydSteinberg::new(lumas, area.size.width as usize)),
+            mode => self.target.fill_contiguous(
+                area,
+                area.points()
+                    .zip(lumas)
+                    .map(|(p, luma)| mode.ordered(p, luma)),
+            ),
+        }
+    }
+}
+
+/// Row by row Floyd–Steinberg error diffusion over the colors of an area.
+struct FloydSteinberg<I> {
+    lumas: I,
+    width: usize,
+    x: usize,
+    current: [i16; MAX_DIFFUSION_WIDTH],
+    next: [i16; MAX_DIFFUSION_WIDTH],
+}
+
+impl<I> FloydSteinberg<I> {
+    fn new(lumas: I, width: usize) -> Self {
+        Self {
+            lumas,
+            width,
+            x: 0,
+            current: [0; MAX_DIFFUSION_WIDTH],
+            next: [0; MAX_DIFFUSION_WIDTH],
+        }
+    }
+
+    /// Spreads the error of pixel `x` to its right and lower neighbours.
+    fn diffuse(&mut self, x: usize, error: i16) {
+        let width = self.width.min(MAX_DIFFUSION_WIDTH);
+        if x + 1 < width {
+            self.current[x + 1] += error * 7 / 16;
+            self.next[x + 1] += error / 16;
+        }
+        if x > 0 {
+            self.next[x - 1] += error * 3 / 16;
+        }
+        self.next[x] += error * 5 / 16;
+    }
+}
+
+impl<I: Iterator<Item = u8>> Iterator for FloydSteinberg<I> {
+    type Item = BinaryColor;
+
+    fn next(&mut self) -> Option<BinaryColor> {
+        let luma = self.lumas.next()? as i16;
+        let x = self.x;
+
+        let on = if x < MAX_DIFFUSION_WIDTH {
+            let value = luma + self.current[x];
+            let on = value >= 128;
+            self.diffuse(x, value - if on { 255 } else { 0 });
+            on
+        } else {
+            luma >= 128
+        };
+
+        self.x += 1;
+        if self.x >= self.width {
+            self.x = 0;
+            self.current = self.next;
+            self.next = [0; MAX_DIFFUSION_WIDTH];
+        }
+        Some(BinaryColor::from(on))
+    }
+}
@@ -0,0 +1,87 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Double buffering between a game loop and a display task.
+//!
+//! The game draws into its own back [`Framebuffer`] and calls [`DoubleBuffer::present`],
+//! which copies the changed lines into the shared front buffer and returns. A display
+//! task streams the front buffer to the panel while the game works on the next frame.
+//!
+//! ```ignore
+//! static FRAMES: StaticCell<DoubleBuffer<CriticalSectionRawMutex>> = StaticCell::new();
+//! let frames = FRAMES.init(DoubleBuffer::new());
+//! spawner.spawn(kywy::display::display_task(display, frames)).unwrap();
+//!
+//! let mut back = Framebuffer::new();
+//! loop {
+//!     draw_game(&mut back);
+//!     frames.present(&back).await;
+//! }
+//! ```
+
+use crate::framebuffer::Framebuffer;
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::mutex::{Mutex, MutexGuard};
+use embassy_sync::signal::Signal;
+
+pub struct DoubleBuffer<M: RawMutex> {
+    front: Mutex<M, Framebuffer>,
+    presented: Signal<M, ()>,
+    complete: Signal<M, ()>,
+}
+
+impl<M: RawMutex> Default for DoubleBuffer<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: RawMutex> DoubleBuffer<M> {
+    pub fn new() -> Self {
+        Self {
+            front: Mutex::new(Framebuffer::new()),
+            presented: Signal::new(),
+            complete: Signal::new(),
+        }
+    }
+
+    /// Hands a finished frame to the display task.
+    /// Only waits if the previous frame is still being sent. If the display task has
+    /// not picked up the previous frame yet it is replaced, no lines are lost.
+    pub async fn present(&self, back: &Framebuffer) {
+        self.front.lock().await.copy_from(back);
+        self.presented.signal(());
+    }
+
+    /// Waits until every presented frame has been sent to the panel.
+    /// Lines that fail to send are retried by the display task until they go out.
+    pub async fn wait_frame_complete(&self) {
+        // the front buffer is clean once sent, the signal only wakes us up to check
+        while self.front.lock().await.is_dirty() {
+            self.complete.wait().await;
+        }
+    }
+
+    /// Returns true if every presented frame has been sent to the panel.
+    pub fn is_frame_complete(&self) -> bool {
+        self.front.try_lock().is_ok_and(|front| !front.is_dirty())
+    }
+
+    /// Display task side: waits for a presented frame and locks the front buffer.
+    pub async fn next_frame(&self) -> MutexGuard<'_, M, Framebuffer> {
+        self.presented.wait().await;
+        self.front.lock().await
+    }
+
+    /// Display task side: locks the front buffer without waiting for a new frame,
+    /// to resend lines that failed.
+    pub async fn front(&self) -> MutexGuard<'_, M, Framebuffer> {
+        self.front.lock().await
+    }
+
+    /// Display task side: wakes up tasks waiting for the frame to complete.
+    pub fn finish_frame(&self) {
+        self.complete.signal(());
+    }
+}
@@ -0,0 +1,317 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Camera for Kywy game engine
+//! Games place everything in world coordinates, which can be much larger than the screen.
+//! The camera decides which part of the world is visible and [`CameraView`] draws through it:
+//!
+//! ```ignore
+//! let mut camera = Camera::default()
+//!     .with_bounds(Rectangle::new(Point::zero(), Size::new(512, 168)))
+//!     .with_deadzone(Size::new(40, 40));
+//! loop {
+//!     camera.follow(player.position + Point::new(32, 32));
+//!     camera.update(dt);
+//!     renderer.instance(1, &player, SpriteOptions::default()).ok();
+//!     renderer.render_dirty(&mut camera.view(&mut display)).unwrap();
+//!     // screen space HUD goes straight to the display
+//!     display.write_display().await.ok();
+//! }
+//! ```
+
+use crate::framebuffer::{HEIGHT, WIDTH};
+use embassy_time::Duration;
+use embedded_graphics::{
+    Pixel,
+    draw_target::DrawTarget,
+    geometry::{Dimensions, Point, Size},
+    image::GetPixel,
+    primitives::{PointsIter, Rectangle},
+    transform::Transform,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    /// World coordinate shown at the top left of the screen, not counting shake
+    pub position: Point,
+    size: Size,
+    bounds: Option<Rectangle>,
+    deadzone: Size,
+    shake: Shake,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Shake {
+    magnitude: u8,
+    duration: Duration,
+    remaining: Duration,
+    offset: Point,
+    seed: u32,
+}
+
+impl Default for Camera {
+    /// A camera the size of the portrait screen at the world origin.
+    fn default() -> Self {
+        Self::new(Size::new(WIDTH as u32, HEIGHT as u32))
+    }
+}
+
+impl Camera {
+    /// Creates a camera showing `size` pixels of the world, without bounds or deadzone.
+    pub fn new(size: Size) -> Self {
+        Self {
+            position: Point::zero(),
+            size,
+            bounds: None,
+            deadzone: Size::zero(),
+            shake: Shake {
+                magnitude: 0,
+                duration: Duration::from_ticks(0),
+                remaining: Duration::from_ticks(0),
+                offset: Point::zero(),
+                seed: 0x9e37_79b9,
+            },
+        }
+    }
+
+    /// Keeps the view inside `bounds`, usually the level's size. An axis where the
+    /// bounds are smaller than the view is centered on them instead.
+    pub fn with_bounds(mut self, bounds: Rectangle) -> Self {
+        self.set_bounds(Some(bounds));
+        self
+    }
+
+    /// Area around the screen center that [`follow`](Self::follow) lets the target move
+    /// in without scrolling.
+    pub fn with_deadzone(mut self, deadzone: Size) -> Self {
+        self.deadzone = deadzone;
+        self
+    }
+
+    /// Starts the shake offsets from `seed`, so a replay shakes the same way.
+    pub fn with_shake_seed(mut self, seed: u32) -> Self {
+        // xorshift never leaves zero
+        self.shake.seed = seed.max(1);
+        self
+    }
+
+    pub fn set_bounds(&mut self, bounds: Option<Rectangle>) {
+        self.bounds = bounds;
+        self.clamp();
+    }
+
+    pub fn bounds(&self) -> Option<Rectangle> {
+        self.bounds
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Changes the visible size, e.g. after rotating the display to landscape.
+    pub fn set_size(&mut self, size: Size) {
+        self.size = size;
+        self.clamp();
+    }
+
+    /// Moves the top left of the view to `position`, within the bounds.
+    pub fn set_position(&mut self, position: Point) {
+        self.position = position;
+        self.clamp();
+    }
+
+    pub fn move_by(&mut self, dx: i32, dy: i32) {
+        self.set_position(self.position + Point::new(dx, dy));
+    }
+
+    /// Centers the view on `target`, within the bounds.
+    pub fn look_at(&mut self, target: Point) {
+        self.set_position(target - self.size / 2);
+    }
+
+    /// Scrolls just enough to bring `target` back into the deadzone.
+    /// Without a deadzone this keeps the target centered.
+    pub fn follow(&mut self, target: Point) {
+        let zone = Rectangle::with_center(self.position + self.size / 2, self.deadzone);
+        let Some(bottom_right) = zone.bottom_right() else {
+            return self.look_at(target);
+        };
+        let dx = (target.x - zone.top_left.x).min(0) + (target.x - bottom_right.x).max(0);
+        let dy = (target.y - zone.top_left.y).min(0) + (target.y - bottom_right.y).max(0);
+        self.move_by(dx, dy);
+    }
+
+    /// Shakes the view up to `magnitude` pixels in each direction, calming down over `duration`.
+    /// Replaces a shake that is still running.
+    pub fn shake(&mut self, magnitude: u8, duration: Duration) {
+        self.shake.magnitude = magnitude;
+        self.shake.duration = duration;
+        self.shake.remaining = duration;
+    }
+
+    pub fn is_shaking(&self) -> bool {
+        self.shake.remaining.as_ticks() > 0
+    }
+
+    /// Advances the shake by `dt`, call once per frame.
+    pub fn update(&mut self, dt: Duration) {
+        let shake = &mut self.shake;
+        shake.remaining = shake
+            .remaining
+            .checked_sub(dt)
+            .unwrap_or(Duration::from_ticks(0));
+        if shake.remaining.as_ticks() == 0 {
+            shake.offset = Point::zero();
+            return;
+        }
+        let range = (shake.magnitude as u64 * shake.remaining.as_ticks()
+            / shake.duration.as_ticks().max(1)) as i32;
+        let mut next = || {
+            // xorshift32, a different looking offset every frame is all that's needed
+            shake.seed ^= shake.seed << 13;
+            shake.seed ^= shake.seed >> 17;
+            shake.seed ^= shake.seed << 5;
+            (shake.seed % (2 * range as u32 + 1)) as i32 - range
+        };
+        shake.offset = Point::new(next(), next());
+    }
+
+    /// World coordinate drawn at the top left of the screen, including shake.
+    pub fn offset(&self) -> Point {
+        self.position + self.shake.offset
+    }
+
+    /// Part of the world that is visible.
+    pub fn viewport(&self) -> Rectangle {
+        Rectangle::new(self.offset(), self.size)
+    }
+
+    pub fn to_screen(&self, world: Point) -> Point {
+        world - self.offset()
+    }
+
+    pub fn to_world(&self, screen: Point) -> Point {
+        screen + self.offset()
+    }
+
+    /// Returns true if any of `area` (in world coordinates) is on screen.
+    pub fn is_visible(&self, area: &Rectangle) -> bool {
+        !self.viewport().intersection(area).is_zero_sized()
+    }
+
+    /// Wraps `target` so drawing in world coordinates lands where this camera shows it.
+    pub fn view<'t, D>(&self, target: &'t mut D) -> CameraView<'t, D>
+    where
+        D: DrawTarget,
+    {
+        let screen = target
+            .bounding_box()
+            .intersection(&Rectangle::new(Point::zero(), self.size));
+        CameraView {
+            target,
+            offset: self.offset(),
+            screen,
+        }
+    }
+
+    fn clamp(&mut self) {
+        let Some(bounds) = self.bounds else {
+            return;
+        };
+        let axis = |position: i32, view: u32, start: i32, length: u32| {
+            if view >= length {
+                start - (view - length) as i32 / 2
+            } else {
+                position.clamp(start, start + (length - view) as i32)
+            }
+        };
+        self.position = Point::new(
+            axis(
+                self.position.x,
+                self.size.width,
+                bounds.top_left.x,
+                bounds.size.width,
+            ),
+            axis(
+                self.position.y,
+                self.size.height,
+                bounds.top_left.y,
+                bounds.size.height,
+            ),
+        );
+    }
+}
+
+/// Draw target in world coordinates, created by [`Camera::view`].
+/// Everything is moved by the camera offset and clipped to the camera's size; its bounding
+/// box is the visible part of the world, so clearing it only clears the camera's area.
+pub struct CameraView<'t, D> {
+    target: &'t mut D,
+    offset: Point,
+    /// Area of the target the camera draws to
+    screen: Rectangle,
+}
+
+impl<D> CameraView<'_, D> {
+    fn clip(&self, area: &Rectangle) -> Rectangle {
+        self.screen.intersection(&area.translate(-self.offset))
+    }
+}
+
+impl<D> Dimensions for CameraView<'_, D> {
+    fn bounding_box(&self) -> Rectangle {
+        self.screen.translate(self.offset)
+    }
+}
+
+impl<D: DrawTarget> DrawTarget for CameraView<'_, D> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (offset, screen) = (self.offset, self.screen);
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(point, color)| Pixel(point - offset, color))
+                .filter(|Pixel(point, _)| screen.contains(*point)),
+        )
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let moved = area.translate(-self.offset);
+        if self.clip(area) == moved {
+            self.target.fill_contiguous(&moved, colors)
+        } else {
+            self.draw_iter(
+                area.points()
+                    .zip(colors)
+                    .map(|(point, color)| Pixel(point, color)),
+            )
+        }
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let clipped = self.clip(area);
+        self.target.fill_solid(&clipped, color)
+    }
+}
+
+impl<D: GetPixel> GetPixel for CameraView<'_, D> {
+    type Color = D::Color;
+
+    fn pixel(&self, p: Point) -> Option<Self::Color> {
+        let p = p - self.offset;
+        if self.screen.contains(p) {
+            self.target.pixel(p)
+        } else {
+            None
+        }
+    }
+}
@@ -3,5 +3,8 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 //! Engine for making games with Kywy.
 
+pub mod camera;
 pub mod renderer;
 pub mod sprite;
+pub mod tilemap;
+pub mod transition;
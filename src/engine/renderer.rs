@@ -3,3 +3,510 @@
 
 //! Renderer for Kywy game engine
 //! Takes sprites and renders them to the screen handeling order and transparency.
+//!
+//! Games submit everything visible in a frame with a layer, lower layers are drawn
+//! first and items on the same layer keep their submission order:
+//!
+//! ```ignore
+//! let mut renderer: Renderer<16> = Renderer::new();
+//! renderer.image(0, &background, Point::zero());
+//! renderer.instance(1, &player, SpriteOptions::default());
+//! renderer.text(2, "Score", Point::new(2, 10), text_style);
+//! renderer.render(&mut display).unwrap();
+//! display.write_display().await.ok();
+//! ```
+//!
+//! [`Renderer::render_dirty`] keeps what was drawn last frame and only clears and redraws
+//! the areas that changed since. The framebuffer marks just the lines that really change,
+//! so `write_display` then transmits only those lines instead of the whole panel.
+
+use crate::engine::sprite::{Sprite, SpriteInstance, SpriteOptions};
+use core::hash::{Hash, Hasher};
+use embedded_graphics::{
+    Drawable, Pixel,
+    draw_target::DrawTarget,
+    geometry::{Dimensions, OriginDimensions, Point, Size},
+    image::{GetPixel, Image},
+    mono_font::MonoTextStyle,
+    pixelcolor::BinaryColor,
+    primitives::{Circle, Line, PointsIter, Primitive, PrimitiveStyle, Rectangle},
+    text::Text,
+};
+use heapless::Vec;
+use tinybmp::Bmp;
+
+/// Render list entries
+pub enum DrawCommand<'a> {
+    Sprite {
+        sprite: Sprite<'a>,
+        pos: Point,
+        options: SpriteOptions,
+    },
+    Image {
+        bmp: &'a Bmp<'a, BinaryColor>,
+        pos: Point,
+    },
+    Rectangle(Rectangle, PrimitiveStyle<BinaryColor>),
+    Circle(Circle, PrimitiveStyle<BinaryColor>),
+    Line(Line, PrimitiveStyle<BinaryColor>),
+    Text(Text<'a, MonoTextStyle<'a, BinaryColor>>),
+}
+
+impl DrawCommand<'_> {
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor> + GetPixel<Color = BinaryColor>,
+    {
+        match self {
+            DrawCommand::Sprite {
+                sprite,
+                pos,
+                options,
+            } => sprite.draw_blended(target, *pos, *options),
+            DrawCommand::Image { bmp, pos } => Image::new(*bmp, *pos).draw(target),
+            DrawCommand::Rectangle(rect, style) => rect.into_styled(*style).draw(target),
+            DrawCommand::Circle(circle, style) => circle.into_styled(*style).draw(target),
+            DrawCommand::Line(line, style) => line.into_styled(*style).draw(target),
+            DrawCommand::Text(text) => text.draw(target).map(|_| ()),
+        }
+    }
+
+    /// Area the command can touch.
+    fn area(&self) -> Rectangle {
+        match self {
+            DrawCommand::Sprite {
+                sprite,
+                pos,
+                options,
+            } => sprite.bounding_box(*pos, *options),
+            DrawCommand::Image { bmp, pos } => Rectangle::new(*pos, OriginDimensions::size(*bmp)),
+            DrawCommand::Rectangle(rect, style) => rect.into_styled(*style).bounding_box(),
+            DrawCommand::Circle(circle, style) => circle.into_styled(*style).bounding_box(),
+            DrawCommand::Line(line, style) => line.into_styled(*style).bounding_box(),
+            DrawCommand::Text(text) => text.bounding_box(),
+        }
+    }
+
+    /// Hashes everything that decides what the command draws. Images, sprite sheets
+    /// and fonts are borrowed for the whole frame, so their address stands in for the pixels.
+    fn fingerprint(&self) -> u64 {
+        let mut hasher = Fnv::default();
+        match self {
+            DrawCommand::Sprite {
+                sprite,
+                pos,
+                options,
+            } => {
+                0u8.hash(&mut hasher);
+                sprite.id().hash(&mut hasher);
+                pos.hash(&mut hasher);
+                options.hash(&mut hasher);
+            }
+            DrawCommand::Image { bmp, pos } => {
+                1u8.hash(&mut hasher);
+                core::ptr::from_ref(*bmp).hash(&mut hasher);
+                pos.hash(&mut hasher);
+            }
+            DrawCommand::Rectangle(rect, style) => {
+                2u8.hash(&mut hasher);
+                rect.hash(&mut hasher);
+                style.hash(&mut hasher);
+            }
+            DrawCommand::Circle(circle, style) => {
+                3u8.hash(&mut hasher);
+                circle.hash(&mut hasher);
+                style.hash(&mut hasher);
+            }
+            DrawCommand::Line(line, style) => {
+                4u8.hash(&mut hasher);
+                line.hash(&mut hasher);
+                style.hash(&mut hasher);
+            }
+            DrawCommand::Text(text) => {
+                // MonoTextStyle doesn't implement Hash, so go through its fields
+                let style = &text.character_style;
+                5u8.hash(&mut hasher);
+                text.text.hash(&mut hasher);
+                text.position.hash(&mut hasher);
+                text.text_style.hash(&mut hasher);
+                core::ptr::from_ref(style.font).hash(&mut hasher);
+                style.text_color.hash(&mut hasher);
+                style.background_color.hash(&mut hasher);
+                style.underline_color.hash(&mut hasher);
+                style.strikethrough_color.hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+}
+
+/// 64 bit FNV-1a, small and good enough to tell draw commands apart.
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+struct Item<'a> {
+    layer: i8,
+    command: DrawCommand<'a>,
+}
+
+/// What an item left on screen, kept between frames to find what changed.
+#[derive(Clone, Copy, PartialEq)]
+struct Footprint {
+    layer: i8,
+    area: Rectangle,
+    fingerprint: u64,
+}
+
+impl Footprint {
+    fn of(item: &Item) -> Self {
+        Self {
+            layer: item.layer,
+            area: item.command.area(),
+            fingerprint: item.command.fingerprint(),
+        }
+    }
+}
+
+/// Most separate areas [`Renderer::render_dirty`] keeps apart, more are merged.
+pub const MAX_DIRTY_RECTS: usize = 4;
+
+/// Changed areas are merged when they are fewer than this many pixels apart, redrawing
+/// the gap costs less than going through the commands once more.
+const MERGE_DISTANCE: i32 = 8;
+
+/// Separate areas redrawn by [`Renderer::render_dirty`], empty when nothing changed.
+pub type DirtyRects = Vec<Rectangle, MAX_DIRTY_RECTS>;
+
+/// Adds `area` to `dirty`, merged with every area it overlaps or comes close to.
+/// A full list takes it into the area that grows the least.
+fn add_dirty(dirty: &mut DirtyRects, mut area: Rectangle) {
+    if area.is_zero_sized() {
+        return;
+    }
+    // a merged area can reach others that the parts did not
+    while let Some(i) = dirty.iter().position(|other| {
+        !area
+            .offset(MERGE_DISTANCE)
+            .intersection(other)
+            .is_zero_sized()
+    }) {
+        area = union(area, dirty.swap_remove(i));
+    }
+    if let Err(area) = dirty.push(area) {
+        let pixels = |r: Rectangle| r.size.width * r.size.height;
+        let growth = |other: &Rectangle| pixels(union(area, *other)) - pixels(*other);
+        // the list is full, so it has a smallest growth
+        if let Some(i) = (0..dirty.len()).min_by_key(|&i| growth(&dirty[i])) {
+            let merged = union(area, dirty.swap_remove(i));
+            add_dirty(dirty, merged);
+        }
+    }
+}
+
+/// Smallest rectangle covering both, empty rectangles are ignored.
+fn union(a: Rectangle, b: Rectangle) -> Rectangle {
+    if a.is_zero_sized() {
+        return b;
+    }
+    if b.is_zero_sized() {
+        return a;
+    }
+    let top_left = a.top_left.component_min(b.top_left);
+    let bottom_right = (a.top_left + a.size).component_max(b.top_left + b.size);
+    let size = bottom_right - top_left;
+    Rectangle::new(top_left, Size::new(size.x as u32, size.y as u32))
+}
+
+/// Draw target that drops everything outside of `area`. Unlike `DrawTargetExt::clipped`
+/// it keeps the target's coordinates and can be read back for blended sprites.
+struct Clip<'t, D> {
+    target: &'t mut D,
+    area: Rectangle,
+}
+
+impl<D: Dimensions> Dimensions for Clip<'_, D> {
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<D: DrawTarget> DrawTarget for Clip<'_, D> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let area = self.area;
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .filter(|Pixel(point, _)| area.contains(*point)),
+        )
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        if self.area.intersection(area) == *area {
+            self.target.fill_contiguous(area, colors)
+        } else {
+            self.draw_iter(
+                area.points()
+                    .zip(colors)
+                    .map(|(point, color)| Pixel(point, color)),
+            )
+        }
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.target.fill_solid(&self.area.intersection(area), color)
+    }
+}
+
+impl<D: GetPixel> GetPixel for Clip<'_, D> {
+    type Color = D::Color;
+
+    fn pixel(&self, p: Point) -> Option<Self::Color> {
+        self.target.pixel(p)
+    }
+}
+
+/// Fixed capacity render list for one frame, holding up to `N` draw commands.
+/// Adding to a full list hands the command back as the error.
+pub struct Renderer<'a, const N: usize = 32> {
+    items: Vec<Item<'a>, N>,
+    background: Option<BinaryColor>,
+    /// Target area and what the last render left on it, `None` until the next render is a full one
+    previous: Option<(Rectangle, Vec<Footprint, N>)>,
+}
+
+impl<const N: usize> Default for Renderer<'_, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, const N: usize> Renderer<'a, N> {
+    /// Creates an empty list that clears the screen to white before drawing.
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            background: Some(BinaryColor::On),
+            previous: None,
+        }
+    }
+
+    /// Color the target is cleared to before drawing, `None` draws over the previous frame.
+    /// Changing it makes the next [`render_dirty`](Self::render_dirty) redraw everything.
+    pub fn set_background(&mut self, background: Option<BinaryColor>) {
+        if background != self.background {
+            self.invalidate();
+        }
+        self.background = background;
+    }
+
+    pub fn background(&self) -> Option<BinaryColor> {
+        self.background
+    }
+
+    pub fn push(&mut self, layer: i8, command: DrawCommand<'a>) -> Result<(), DrawCommand<'a>> {
+        self.items
+            .push(Item { layer, command })
+            .map_err(|item| item.command)
+    }
+
+    pub fn sprite(
+        &mut self,
+        layer: i8,
+        sprite: Sprite<'a>,
+        pos: Point,
+        options: SpriteOptions,
+    ) -> Result<(), DrawCommand<'a>> {
+        self.push(
+            layer,
+            DrawCommand::Sprite {
+                sprite,
+                pos,
+                options,
+            },
+        )
+    }
+
+    /// Adds the current frame of `instance` at its position.
+    pub fn instance<S: Copy + Eq, const M: usize>(
+        &mut self,
+        layer: i8,
+        instance: &SpriteInstance<'a, S, M>,
+        options: SpriteOptions,
+    ) -> Result<(), DrawCommand<'a>> {
+        match instance.current().current_frame_sprite() {
+            Some(sprite) => self.sprite(layer, sprite, instance.position, options),
+            None => Ok(()),
+        }
+    }
+
+    pub fn image(
+        &mut self,
+        layer: i8,
+        bmp: &'a Bmp<'a, BinaryColor>,
+        pos: Point,
+    ) -> Result<(), DrawCommand<'a>> {
+        self.push(layer, DrawCommand::Image { bmp, pos })
+    }
+
+    pub fn rectangle(
+        &mut self,
+        layer: i8,
+        rect: Rectangle,
+        style: PrimitiveStyle<BinaryColor>,
+    ) -> Result<(), DrawCommand<'a>> {
+        self.push(layer, DrawCommand::Rectangle(rect, style))
+    }
+
+    pub fn circle(
+        &mut self,
+        layer: i8,
+        circle: Circle,
+        style: PrimitiveStyle<BinaryColor>,
+    ) -> Result<(), DrawCommand<'a>> {
+        self.push(layer, DrawCommand::Circle(circle, style))
+    }
+
+    pub fn line(
+        &mut self,
+        layer: i8,
+        line: Line,
+        style: PrimitiveStyle<BinaryColor>,
+    ) -> Result<(), DrawCommand<'a>> {
+        self.push(layer, DrawCommand::Line(line, style))
+    }
+
+    /// Adds `text` with its baseline starting at `pos`.
+    pub fn text(
+        &mut self,
+        layer: i8,
+        text: &'a str,
+        pos: Point,
+        style: MonoTextStyle<'a, BinaryColor>,
+    ) -> Result<(), DrawCommand<'a>> {
+        self.push(layer, DrawCommand::Text(Text::new(text, pos, style)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Drops every submitted command without drawing.
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// Makes the next [`render_dirty`](Self::render_dirty) redraw the whole target,
+    /// needed when something else drew to it since the last render.
+    pub fn invalidate(&mut self) {
+        self.previous = None;
+    }
+
+    /// Clears the target to the background and draws every command, lowest layer first.
+    /// The list is empty afterwards, ready for the next frame.
+    pub fn render<D>(&mut self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor> + GetPixel<Color = BinaryColor>,
+    {
+        self.invalidate();
+        self.render_dirty(target).map(|_| ())
+    }
+
+    /// Like [`render`](Self::render) but only redraws where the list differs from the
+    /// last render: commands that were added, removed, moved or changed. Their areas
+    /// are collected into up to [`MAX_DIRTY_RECTS`] separate rectangles, merging those
+    /// that overlap or are close. Each is cleared to the background and every command
+    /// touching it is drawn again, clipped to it. Returns the rectangles, none when
+    /// nothing changed.
+    ///
+    /// A target whose bounding box moved, like a scrolled [`CameraView`], is redrawn
+    /// completely. Without a background the old position of a moved command is not erased.
+    /// Reordering identical commands on the same layer is not detected.
+    ///
+    /// [`CameraView`]: crate::engine::camera::CameraView
+    pub fn render_dirty<D>(&mut self, target: &mut D) -> Result<DirtyRects, D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor> + GetPixel<Color = BinaryColor>,
+    {
+        let footprints: Vec<Footprint, N> = self.items.iter().map(Footprint::of).collect();
+        let bounds = target.bounding_box();
+        let mut dirty = DirtyRects::new();
+        match self.previous.replace((bounds, footprints.clone())) {
+            Some((previous_bounds, previous)) if previous_bounds == bounds => {
+                let added = footprints.iter().filter(|f| !previous.contains(f));
+                let removed = previous.iter().filter(|f| !footprints.contains(f));
+                for footprint in added.chain(removed) {
+                    add_dirty(&mut dirty, footprint.area.intersection(&bounds));
+                }
+            }
+            _ => add_dirty(&mut dirty, bounds),
+        }
+        if dirty.is_empty() {
+            self.items.clear();
+            return Ok(dirty);
+        }
+
+        // the position in the list breaks ties, so equal layers keep submission order
+        let mut order: Vec<(i8, u16), N> = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (item.layer, i as u16))
+            .collect();
+        order.sort_unstable();
+
+        let result = dirty.iter().try_for_each(|&area| {
+            let mut clip = Clip {
+                target: &mut *target,
+                area,
+            };
+            if let Some(color) = self.background {
+                clip.fill_solid(&area, color)?;
+            }
+            order
+                .iter()
+                .filter(|&&(_, i)| {
+                    !footprints[i as usize]
+                        .area
+                        .intersection(&area)
+                        .is_zero_sized()
+                })
+                .try_for_each(|&(_, i)| self.items[i as usize].command.draw(&mut clip))
+        });
+        self.items.clear();
+        if result.is_err() {
+            // the target is only partly drawn, start over next frame
+            self.invalidate();
+        }
+        result.map(|_| dirty)
+    }
+}
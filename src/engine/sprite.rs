@@ -3,30 +3,234 @@
 
 //! Sprite handler for Kywy game engine
 
-use embedded_graphics::Drawable;
+use core::ops::{BitOr, BitOrAssign};
+use embassy_time::Duration;
 use embedded_graphics::geometry::{Point, Size};
-use embedded_graphics::image::{Image, ImageRaw};
+use embedded_graphics::image::GetPixel;
 use embedded_graphics::pixelcolor::BinaryColor;
 use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
 use heapless::Vec;
-use tinybmp::{Bmp, ParseError};
+use tinybmp::{Bpp, CompressionMethod, RawBmp, RowOrder};
 
-/// A sprite sheet made from a BMP image
+pub use tinybmp::ParseError;
+
+/// A sprite sheet made from a 1bpp BMP image
+/// Frames are read a byte at a time straight out of the BMP's pixel data, so drawing
+/// a sprite only touches its own pixels. This direct reader is the fast path, a
+/// decoded copy of the frame measured no faster as most of the time goes into the target.
+/// An optional mask sheet with the same layout marks the opaque pixels (On).
 #[derive(Debug)]
 pub struct SpriteSheet<'a> {
-    bmp: Bmp<'a, BinaryColor>,
+    pixels: Bits<'a>,
+    mask: Option<Bits<'a>>,
     sprite_size: Size,
     sheet_size: Size,
 }
 
-#[derive(Clone, Copy)]
+/// Packed 1bpp rows of a BMP, most significant bit first.
+#[derive(Clone, Copy, Debug)]
+struct Bits<'s> {
+    data: &'s [u8],
+    bytes_per_row: usize,
+    /// Size of the whole image, without the row padding
+    size: Size,
+    /// BMPs usually store the bottom row first
+    bottom_up: bool,
+    /// Pixel that `(0, 0)` refers to, the top left of a frame
+    origin: (u32, u32),
+    /// Applied to every byte to turn palette indices into On bits
+    and: u8,
+    xor: u8,
+}
+
+impl<'s> Bits<'s> {
+    /// Pixel data of an uncompressed 1bpp BMP.
+    fn from_bmp(data: &'s [u8]) -> Result<Self, ParseError> {
+        let bmp = RawBmp::from_slice(data)?;
+        let header = bmp.header();
+        if header.bpp != Bpp::Bits1 {
+            return Err(ParseError::UnsupportedBpp(header.bpp.bits()));
+        }
+        if header.compression_method != CompressionMethod::Rgb {
+            return Err(ParseError::UnsupportedCompressionMethod(
+                header.compression_method as u32,
+            ));
+        }
+        let Size { width, height } = header.image_size;
+        // rows are padded to whole 32 bit words
+        let bytes_per_row = width.div_ceil(32) as usize * 4;
+        if bmp.image_data().len() < bytes_per_row * height as usize {
+            return Err(ParseError::UnexpectedEndOfFile);
+        }
+
+        // the palette decides which bit value is On, without one nothing is
+        let on = |index| {
+            bmp.color_table()
+                .and_then(|table| table.get(index))
+                .is_some_and(|color| BinaryColor::from(color).is_on())
+        };
+        let (and, xor) = match (on(0), on(1)) {
+            (false, true) => (0xFF, 0x00),
+            (true, false) => (0xFF, 0xFF),
+            (false, false) => (0x00, 0x00),
+            (true, true) => (0x00, 0xFF),
+        };
+
+        Ok(Self {
+            data: bmp.image_data(),
+            bytes_per_row,
+            size: header.image_size,
+            bottom_up: header.row_order == RowOrder::BottomUp,
+            origin: (0, 0),
+            and,
+            xor,
+        })
+    }
+
+    /// The same pixels with `(0, 0)` moved to `origin`.
+    fn at(self, origin: (u32, u32)) -> Self {
+        Self { origin, ..self }
+    }
+
+    /// Eight pixels of row `y` from column `x` on, the first in the top bit.
+    /// Pixels past the end of the row are undefined.
+    fn byte(&self, x: u32, y: u32) -> u8 {
+        let y = self.origin.1 + y;
+        let row = if self.bottom_up {
+            self.size.height - 1 - y
+        } else {
+            y
+        };
+        let bit = (self.origin.0 + x) as usize;
+        let index = row as usize * self.bytes_per_row + bit / 8;
+        let shift = bit % 8;
+
+        let mut byte = self.data.get(index).copied().unwrap_or(0) << shift;
+        if shift > 0 {
+            byte |= self.data.get(index + 1).copied().unwrap_or(0) >> (8 - shift);
+        }
+        (byte & self.and) ^ self.xor
+    }
+
+    fn bit(&self, x: u32, y: u32) -> bool {
+        self.byte(x, y) & 0x80 != 0
+    }
+}
+
+/// One frame of a sheet, read from the sheet's rows moved to the frame's corner.
+pub struct SpriteFrame<'s> {
+    size: Size,
+    pixels: Bits<'s>,
+    mask: Option<Bits<'s>>,
+}
+
+impl SpriteFrame<'_> {
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Returns true if the pixel at `x`, `y` of the untransformed frame is On.
+    pub fn is_on(&self, x: u32, y: u32) -> bool {
+        self.pixels.bit(x, y)
+    }
+
+    /// Returns false if the mask hides the pixel, frames without a mask are opaque.
+    pub fn is_opaque(&self, x: u32, y: u32) -> bool {
+        self.mask.is_none_or(|mask| mask.bit(x, y))
+    }
+
+    /// Returns true if the pixel at `x`, `y` of the transformed frame gets drawn,
+    /// following the mask and the blend mode.
+    fn is_visible(&self, x: u32, y: u32, options: &SpriteOptions) -> bool {
+        let (x, y) = self.source(x, y, options);
+        self.is_opaque(x, y)
+            && match options.blend {
+                BlendMode::Opaque | BlendMode::Invert => true,
+                BlendMode::TransparentOn => !self.is_on(x, y),
+                BlendMode::TransparentOff | BlendMode::Xor => self.is_on(x, y),
+            }
+    }
+
+    /// Maps a pixel of the transformed frame back to the frame.
+    /// Frames are flipped first and then rotated clockwise.
+    fn source(&self, x: u32, y: u32, options: &SpriteOptions) -> (u32, u32) {
+        let (w, h) = (self.size.width, self.size.height);
+        let (lx, ly) = match options.rotation {
+            Rotation::None => (x, y),
+            Rotation::R90 => (y, h - 1 - x),
+            Rotation::R180 => (w - 1 - x, h - 1 - y),
+            Rotation::R270 => (w - 1 - y, x),
+        };
+        let lx = if options.flip_x { w - 1 - lx } else { lx };
+        let ly = if options.flip_y { h - 1 - ly } else { ly };
+        (lx, ly)
+    }
+
+    /// Eight pixels of row `y` of the transformed frame from column `x` on, the first
+    /// in the top bit. Rows of the frame are read a byte at a time, only the 90 degree
+    /// rotations gather a column bit by bit.
+    fn transformed_byte(&self, pixels: &Bits<'_>, x: u32, y: u32, options: &SpriteOptions) -> u8 {
+        let (lx, ly) = self.source(x, y, options);
+        let reversed = options.flip_x != (options.rotation == Rotation::R180);
+        match options.rotation {
+            Rotation::None | Rotation::R180 if !reversed => pixels.byte(lx, ly),
+            Rotation::None | Rotation::R180 if lx >= 7 => pixels.byte(lx - 7, ly).reverse_bits(),
+            // fewer than eight pixels left towards the start of the row
+            Rotation::None | Rotation::R180 => pixels.byte(0, ly).reverse_bits() << (7 - lx),
+            // the rotated rows are as long as the frame is high
+            Rotation::R90 | Rotation::R270 => {
+                (0..8.min(self.size.height - x)).fold(0, |byte, i| {
+                    let (lx, ly) = self.source(x + i, y, options);
+                    byte | (u8::from(pixels.bit(lx, ly)) << (7 - i))
+                })
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct SpriteOptions {
     pub flip_x: bool,
     pub flip_y: bool,
     pub rotation: Rotation,
+    pub blend: BlendMode,
+    /// Integer upscaling, each pixel becomes a `scale` by `scale` block. 0 is treated as 1.
+    pub scale: u8,
+    /// Clockwise rotation in degrees around the sprite center, applied after `rotation`,
+    /// the flips and `scale`. Uses nearest neighbour sampling, so the drawn area can
+    /// reach outside of the unrotated sprite.
+    pub angle: u16,
+}
+
+/// How sprite pixels are combined with what is already drawn.
+/// Pixels hidden by the sheet's mask are never drawn. `Xor` and `Invert` read the
+/// target, they need [`Sprite::draw_blended`] and a target that implements `GetPixel`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum BlendMode {
+    /// Every pixel is drawn
+    #[default]
+    Opaque,
+    /// Off pixels are transparent
+    TransparentOff,
+    /// On pixels are transparent
+    TransparentOn,
+    /// On pixels invert the target, Off pixels leave it alone.
+    /// Only drawn by [`Sprite::draw_blended`].
+    Xor,
+    /// The target is inverted under every opaque pixel.
+    /// Only drawn by [`Sprite::draw_blended`].
+    Invert,
+}
+
+impl BlendMode {
+    /// Returns true if the mode reads back the target.
+    pub fn reads_target(self) -> bool {
+        matches!(self, BlendMode::Xor | BlendMode::Invert)
+    }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Rotation {
     None,
     R90,
@@ -40,53 +244,84 @@ impl Default for SpriteOptions {
             flip_x: false,
             flip_y: false,
             rotation: Rotation::None,
+            blend: BlendMode::Opaque,
+            scale: 1,
+            angle: 0,
         }
     }
 }
 
 impl<'a> SpriteSheet<'a> {
+    /// Reads a sheet from an uncompressed 1bpp BMP, other formats are rejected.
     pub fn new(bmp_data: &'a [u8], sprite_size: Size) -> Result<Self, ParseError> {
-        let bmp = Bmp::from_slice(bmp_data)?;
-        let bmp_size = bmp.size();
+        let pixels = Bits::from_bmp(bmp_data)?;
         let sheet_size = Size::new(
-            bmp_size.width / sprite_size.width,
-            bmp_size.height / sprite_size.height,
+            pixels.size.width / sprite_size.width,
+            pixels.size.height / sprite_size.height,
         );
 
         Ok(Self {
-            bmp,
+            pixels,
+            mask: None,
             sprite_size,
             sheet_size,
         })
     }
 
+    /// Adds a mask sheet, On pixels in the mask are opaque.
+    pub fn with_mask(mut self, mask_data: &'a [u8]) -> Result<Self, ParseError> {
+        self.mask = Some(Bits::from_bmp(mask_data)?);
+        Ok(self)
+    }
+
     pub fn sprite_count(&self) -> Size {
         self.sheet_size
     }
 
+    pub fn sprite_size(&self) -> Size {
+        self.sprite_size
+    }
+
     pub fn sprite(&self, index_x: u32, index_y: u32) -> Option<Sprite<'_>> {
         if index_x >= self.sheet_size.width || index_y >= self.sheet_size.height {
             return None;
         }
 
-        let offset = Point::new(
-            (index_x * self.sprite_size.width) as i32,
-            (index_y * self.sprite_size.height) as i32,
-        );
-
         Some(Sprite {
             sheet: self,
-            offset,
+            index: (index_x, index_y),
         })
     }
+
+    /// Returns the frame, its pixels are read from the sheet as they are needed.
+    pub fn frame(&self, index_x: u32, index_y: u32) -> SpriteFrame<'_> {
+        let origin = (
+            index_x * self.sprite_size.width,
+            index_y * self.sprite_size.height,
+        );
+        SpriteFrame {
+            size: self.sprite_size,
+            pixels: self.pixels.at(origin),
+            mask: self.mask.map(|mask| mask.at(origin)),
+        }
+    }
 }
 
 pub struct Sprite<'a> {
     sheet: &'a SpriteSheet<'a>,
-    offset: Point,
+    index: (u32, u32),
 }
 
 impl Sprite<'_> {
+    /// Identifies the sheet and frame, two sprites with the same id draw the same pixels.
+    pub(crate) fn id(&self) -> (usize, (u32, u32)) {
+        (core::ptr::from_ref(self.sheet) as usize, self.index)
+    }
+
+    /// Draws the sprite with its top left corner at `pos`.
+    /// `Xor` and `Invert` need to read the target, use [`draw_blended`](Self::draw_blended)
+    /// for them. Passing them here fails a debug assertion, release builds draw them
+    /// like `TransparentOff`.
     pub fn draw<D>(
         &self,
         target: &mut D,
@@ -96,84 +331,553 @@ impl Sprite<'_> {
     where
         D: DrawTarget<Color = BinaryColor>,
     {
-        let w = self.sheet.sprite_size.width as usize;
-        let h = self.sheet.sprite_size.height as usize;
-        let mut framebuffer = [0u8; 64 * 64 / 8];
-
-        for pixel in self.sheet.bmp.pixels() {
-            let Point { x, y } = pixel.0;
-            if x < self.offset.x
-                || y < self.offset.y
-                || x >= self.offset.x + w as i32
-                || y >= self.offset.y + h as i32
-            {
-                continue;
+        debug_assert!(
+            !options.blend.reads_target(),
+            "{:?} needs Sprite::draw_blended",
+            options.blend
+        );
+        let frame = self.sheet.frame(self.index.0, self.index.1);
+        let placement = Placement::new(self.sheet.sprite_size, pos, &options);
+
+        let keep = match options.blend {
+            BlendMode::Opaque => None,
+            BlendMode::TransparentOn => Some(BinaryColor::Off),
+            _ => Some(BinaryColor::On),
+        };
+
+        if !placement.is_rotated() {
+            // flips, rotations and scaling are applied while copying whole bytes of the frame
+            let colors = Blit::new(&frame, frame.pixels, &placement, options);
+            if keep.is_none() && frame.mask.is_none() {
+                return target.fill_contiguous(&placement.area, colors);
             }
+            let mut opaque = frame
+                .mask
+                .map(|mask| Blit::new(&frame, mask, &placement, options));
+            let pixels = placement
+                .area
+                .points()
+                .zip(colors)
+                .filter_map(|(p, color)| {
+                    let opaque = opaque
+                        .as_mut()
+                        .is_none_or(|mask| mask.next().is_some_and(|c| c.is_on()));
+                    (opaque && keep.is_none_or(|keep| keep == color)).then_some(Pixel(p, color))
+                });
+            return target.draw_iter(pixels);
+        }
 
-            // Local sprite coordinates
-            let mut lx = (x - self.offset.x) as usize;
-            let mut ly = (y - self.offset.y) as usize;
+        let pixels = placement.area.points().filter_map(|p| {
+            let (x, y) = placement.local(p)?;
+            let (x, y) = frame.source(x, y, &options);
+            let color = BinaryColor::from(frame.is_on(x, y));
+            (frame.is_opaque(x, y) && keep.is_none_or(|keep| keep == color))
+                .then_some(Pixel(p, color))
+        });
+        target.draw_iter(pixels)
+    }
 
-            // Flip
-            if options.flip_x {
-                lx = w - 1 - lx;
+    /// Draws the sprite like [`draw`](Self::draw), supporting every blend mode.
+    pub fn draw_blended<D>(
+        &self,
+        target: &mut D,
+        pos: Point,
+        options: SpriteOptions,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor> + GetPixel<Color = BinaryColor>,
+    {
+        if !options.blend.reads_target() {
+            return self.draw(target, pos, options);
+        }
+
+        let frame = self.sheet.frame(self.index.0, self.index.1);
+        let placement = Placement::new(self.sheet.sprite_size, pos, &options);
+
+        for p in placement.area.points() {
+            let Some((x, y)) = placement.local(p) else {
+                continue;
+            };
+            if !frame.is_visible(x, y, &options) {
+                continue;
             }
-            if options.flip_y {
-                ly = h - 1 - ly;
+            if let Some(color) = target.pixel(p) {
+                Pixel(p, color.invert()).draw(target)?;
             }
+        }
+        Ok(())
+    }
+
+    /// Area the sprite covers with its top left corner at `pos`.
+    /// With an `angle` this is the box around the rotated sprite.
+    pub fn bounding_box(&self, pos: Point, options: SpriteOptions) -> Rectangle {
+        Placement::new(self.sheet.sprite_size, pos, &options).area
+    }
+
+    /// Returns true if any pixel drawn for this sprite at `pos` is also drawn for `other`
+    /// at `other_pos`. Bounding boxes are checked first, then the pixels that the masks
+    /// and blend modes let through, so an `Opaque` sprite without a mask is solid.
+    pub fn collides_with(
+        &self,
+        pos: Point,
+        options: SpriteOptions,
+        other: &Sprite<'_>,
+        other_pos: Point,
+        other_options: SpriteOptions,
+    ) -> bool {
+        let placement = Placement::new(self.sheet.sprite_size, pos, &options);
+        let other_placement = Placement::new(other.sheet.sprite_size, other_pos, &other_options);
+        let overlap = placement.area.intersection(&other_placement.area);
+        if overlap.is_zero_sized() {
+            return false;
+        }
 
-            // Rotation
-            let (fx, fy) = match options.rotation {
-                Rotation::None => (lx, ly),
-                Rotation::R90 => (h - 1 - ly, lx),
-                Rotation::R180 => (w - 1 - lx, h - 1 - ly),
-                Rotation::R270 => (ly, w - 1 - lx),
+        let frame = self.sheet.frame(self.index.0, self.index.1);
+        let other_frame = other.sheet.frame(other.index.0, other.index.1);
+        overlap.points().any(|p| {
+            let visible = |placement: &Placement, frame: &SpriteFrame, options| {
+                placement
+                    .local(p)
+                    .is_some_and(|(x, y)| frame.is_visible(x, y, options))
             };
+            visible(&placement, &frame, &options)
+                && visible(&other_placement, &other_frame, &other_options)
+        })
+    }
+}
 
-            let idx = fy * w + fx;
-            if pixel.1.is_on() {
-                let byte = idx / 8;
-                let bit = 7 - (idx % 8);
-                framebuffer[byte] |= 1 << bit;
+/// Pixels of a flipped, rotated and scaled frame in the order `fill_contiguous` expects,
+/// taken from the frame eight at a time.
+struct Blit<'f, 's> {
+    frame: &'f SpriteFrame<'s>,
+    pixels: Bits<'f>,
+    options: SpriteOptions,
+    scale: u32,
+    /// Size of the transformed frame before scaling
+    size: Size,
+    /// Next pixel of the transformed frame and how often it was repeated so far
+    x: u32,
+    y: u32,
+    repeat_x: u32,
+    repeat_y: u32,
+    /// The next `left` pixels of the row, the first in the top bit
+    byte: u8,
+    left: u32,
+}
+
+impl<'f, 's> Blit<'f, 's> {
+    fn new(
+        frame: &'f SpriteFrame<'s>,
+        pixels: Bits<'f>,
+        placement: &Placement,
+        options: SpriteOptions,
+    ) -> Self {
+        Self {
+            frame,
+            pixels,
+            options,
+            scale: placement.scale,
+            size: placement.size / placement.scale,
+            x: 0,
+            y: 0,
+            repeat_x: 0,
+            repeat_y: 0,
+            byte: 0,
+            left: 0,
+        }
+    }
+}
+
+impl Iterator for Blit<'_, '_> {
+    type Item = BinaryColor;
+
+    fn next(&mut self) -> Option<BinaryColor> {
+        if self.y >= self.size.height {
+            return None;
+        }
+        if self.left == 0 {
+            self.byte = self
+                .frame
+                .transformed_byte(&self.pixels, self.x, self.y, &self.options);
+            self.left = 8.min(self.size.width - self.x);
+        }
+        let on = self.byte & 0x80 != 0;
+
+        self.repeat_x += 1;
+        if self.repeat_x == self.scale {
+            self.repeat_x = 0;
+            self.byte <<= 1;
+            self.left -= 1;
+            self.x += 1;
+            if self.x == self.size.width {
+                // rows are repeated for scaling too
+                self.x = 0;
+                self.left = 0;
+                self.repeat_y += 1;
+                if self.repeat_y == self.scale {
+                    self.repeat_y = 0;
+                    self.y += 1;
+                }
             }
         }
+        Some(BinaryColor::from(on))
+    }
+}
+
+/// sin of 0 to 90 degrees in 2.14 fixed point
+const SIN_TABLE: [i32; 91] = [
+    0, 286, 572, 857, 1143, 1428, 1713, 1997, 2280, 2563, 2845, 3126, 3406, 3686, 3964, 4240, 4516,
+    4790, 5063, 5334, 5604, 5872, 6138, 6402, 6664, 6924, 7182, 7438, 7692, 7943, 8192, 8438, 8682,
+    8923, 9162, 9397, 9630, 9860, 10087, 10311, 10531, 10749, 10963, 11174, 11381, 11585, 11786,
+    11982, 12176, 12365, 12551, 12733, 12911, 13085, 13255, 13421, 13583, 13741, 13894, 14044,
+    14189, 14330, 14466, 14598, 14726, 14849, 14968, 15082, 15191, 15296, 15396, 15491, 15582,
+    15668, 15749, 15826, 15897, 15964, 16026, 16083, 16135, 16182, 16225, 16262, 16294, 16322,
+    16344, 16362, 16374, 16382, 16384,
+];
+const FIXED_ONE: i32 = 1 << 14;
+
+/// Sine and cosine of `angle` degrees in 2.14 fixed point.
+fn sin_cos(angle: u16) -> (i32, i32) {
+    let sin = |angle: u16| match angle % 360 {
+        a @ 0..=90 => SIN_TABLE[a as usize],
+        a @ 91..=180 => SIN_TABLE[180 - a as usize],
+        a @ 181..=270 => -SIN_TABLE[a as usize - 180],
+        a => -SIN_TABLE[360 - a as usize],
+    };
+    (sin(angle), sin(angle % 360 + 90))
+}
+
+/// Where a sprite ends up on screen once scaled and rotated, and the inverse mapping
+/// from screen pixels back to the frame.
+struct Placement {
+    /// Screen pixels that can be covered
+    area: Rectangle,
+    pos: Point,
+    /// Size after the 90 degree rotation and scaling
+    size: Size,
+    scale: u32,
+    sin: i32,
+    cos: i32,
+}
+
+impl Placement {
+    fn new(sprite_size: Size, pos: Point, options: &SpriteOptions) -> Self {
+        let scale = options.scale.max(1) as u32;
+        let size = match options.rotation {
+            Rotation::None | Rotation::R180 => sprite_size,
+            Rotation::R90 | Rotation::R270 => Size::new(sprite_size.height, sprite_size.width),
+        } * scale;
+        let (sin, cos) = sin_cos(options.angle);
+
+        let area = if sin == 0 && cos == FIXED_ONE {
+            Rectangle::new(pos, size)
+        } else {
+            // box around the rotated corners, kept centered on the sprite
+            let (w, h) = (size.width as i32, size.height as i32);
+            let (s, c) = (sin.abs(), cos.abs());
+            let width = (w * c + h * s + FIXED_ONE - 1) / FIXED_ONE + 1;
+            let height = (w * s + h * c + FIXED_ONE - 1) / FIXED_ONE + 1;
+            let top_left = pos + Point::new((w - width).div_euclid(2), (h - height).div_euclid(2));
+            Rectangle::new(top_left, Size::new(width as u32, height as u32))
+        };
+
+        Self {
+            area,
+            pos,
+            size,
+            scale,
+            sin,
+            cos,
+        }
+    }
+
+    fn is_rotated(&self) -> bool {
+        !(self.sin == 0 && self.cos == FIXED_ONE)
+    }
+
+    /// Pixel of the 90 degree rotated, unscaled frame shown at `p`, if any.
+    fn local(&self, p: Point) -> Option<(u32, u32)> {
+        let (w, h) = (self.size.width as i32, self.size.height as i32);
+        let (x, y) = if self.is_rotated() {
+            // pixel centers relative to the sprite center, in half pixels
+            let dx = 2 * (p.x - self.pos.x) + 1 - w;
+            let dy = 2 * (p.y - self.pos.y) + 1 - h;
+            // rotate back by the angle and return to whole pixels from the top left
+            let x = (dx * self.cos + dy * self.sin + w * FIXED_ONE).div_euclid(2 * FIXED_ONE);
+            let y = (dy * self.cos - dx * self.sin + h * FIXED_ONE).div_euclid(2 * FIXED_ONE);
+            (x, y)
+        } else {
+            (p.x - self.pos.x, p.y - self.pos.y)
+        };
 
-        let used_bytes = w * h / 8;
-        let img_raw = ImageRaw::<BinaryColor>::new(&framebuffer[..used_bytes], w as u32);
-        Image::new(&img_raw, pos).draw(target)
+        ((0..w).contains(&x) && (0..h).contains(&y))
+            .then(|| (x as u32 / self.scale, y as u32 / self.scale))
     }
 }
 
+/// How long each frame is shown unless the animation sets its own durations
+pub const DEFAULT_FRAME_DURATION: Duration = Duration::from_millis(100);
+
+/// Order in which an animation steps through its frames
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PlayMode {
+    /// First to last frame, then stays on the last frame and finishes
+    Once,
+    /// First to last frame, then starts over
+    #[default]
+    Loop,
+    /// First to last frame and back again, repeating
+    PingPong,
+    /// Last to first frame, then stays on the first frame and finishes
+    Reverse,
+}
+
+/// Set of up to 32 game defined events, tagged on animation frames.
+/// Events fire when their frame starts showing, see [`Animation::with_events`].
+///
+/// ```ignore
+/// const FOOTSTEP: AnimationEvents = AnimationEvents::event(0);
+/// const HIT: AnimationEvents = AnimationEvents::event(1);
+/// const WALK_EVENTS: &[AnimationEvents] = &[FOOTSTEP, AnimationEvents::NONE, FOOTSTEP];
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct AnimationEvents(pub u32);
+
+impl AnimationEvents {
+    pub const NONE: Self = Self(0);
+
+    /// The event numbered `id`, from 0 to 31.
+    /// Panics for larger ids, which fails the build when used in a `const`.
+    pub const fn event(id: u8) -> Self {
+        assert!(id < 32, "animation event ids go from 0 to 31");
+        Self(1 << id)
+    }
+
+    /// Returns true if every event in `events` is set.
+    pub const fn contains(self, events: Self) -> bool {
+        self.0 & events.0 == events.0
+    }
+
+    pub const fn union(self, events: Self) -> Self {
+        Self(self.0 | events.0)
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Ids of the events that are set, lowest first.
+    pub fn iter(self) -> impl Iterator<Item = u8> {
+        (0..32).filter(move |&id| self.0 & (1 << id) != 0)
+    }
+}
+
+impl BitOr for AnimationEvents {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        self.union(rhs)
+    }
+}
+
+impl BitOrAssign for AnimationEvents {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = self.union(rhs);
+    }
+}
+
+/// Frames, durations and play mode of an animation without a sheet.
+/// Usually generated at build time, see the `import` module.
+#[derive(Clone, Copy, Debug)]
+pub struct AnimationData<'a> {
+    pub frames: &'a [(u32, u32)],
+    pub durations: &'a [Duration],
+    pub mode: PlayMode,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Animation<'a> {
-    pub sheet: &'a SpriteSheet<'a>, // reference to the sprite sheet
-    pub frames: &'a [(u32, u32)],   // list of (index_x, index_y) for frames
-    pub looped: bool,
+    pub sheet: &'a SpriteSheet<'a>,    // reference to the sprite sheet
+    pub frames: &'a [(u32, u32)],      // list of (index_x, index_y) for frames
+    pub durations: &'a [Duration],     // per frame durations, missing entries use frame_duration
+    pub events: &'a [AnimationEvents], // per frame events, missing entries have none
+    pub frame_duration: Duration,
+    pub mode: PlayMode,
     pub current_frame: usize,
     pub finished: bool,
+    elapsed: Duration,
+    backwards: bool,
+    /// Events of frames entered since the last update
+    pending: AnimationEvents,
 }
 
 impl<'a> Animation<'a> {
     pub fn new(sheet: &'a SpriteSheet<'a>, frames: &'a [(u32, u32)], looped: bool) -> Self {
+        let mode = if looped {
+            PlayMode::Loop
+        } else {
+            PlayMode::Once
+        };
         Self {
             sheet,
             frames,
-            looped,
+            durations: &[],
+            events: &[],
+            frame_duration: DEFAULT_FRAME_DURATION,
+            mode,
             current_frame: 0,
             finished: false,
+            elapsed: Duration::from_ticks(0),
+            backwards: false,
+            pending: AnimationEvents::NONE,
+        }
+    }
+
+    pub fn from_data(sheet: &'a SpriteSheet<'a>, data: &AnimationData<'a>) -> Self {
+        Self::new(sheet, data.frames, false)
+            .with_durations(data.durations)
+            .with_mode(data.mode)
+    }
+
+    pub fn with_mode(mut self, mode: PlayMode) -> Self {
+        self.mode = mode;
+        self.reset();
+        self
+    }
+
+    /// Shows every frame for `duration`.
+    pub fn with_frame_duration(mut self, duration: Duration) -> Self {
+        self.frame_duration = duration;
+        self
+    }
+
+    /// Shows frame `i` for `durations[i]`.
+    pub fn with_durations(mut self, durations: &'a [Duration]) -> Self {
+        self.durations = durations;
+        self
+    }
+
+    /// Tags frame `i` with `events[i]`, reported by [`Animation::update`] when the frame starts.
+    pub fn with_events(mut self, events: &'a [AnimationEvents]) -> Self {
+        self.events = events;
+        self.pending = self.frame_events(self.current_frame);
+        self
+    }
+
+    /// Returns true for modes that never finish.
+    pub fn is_looped(&self) -> bool {
+        matches!(self.mode, PlayMode::Loop | PlayMode::PingPong)
+    }
+
+    /// Starts over from the first frame of the play mode.
+    pub fn reset(&mut self) {
+        self.current_frame = match self.mode {
+            PlayMode::Reverse => self.frames.len().saturating_sub(1),
+            _ => 0,
+        };
+        self.finished = false;
+        self.elapsed = Duration::from_ticks(0);
+        self.backwards = self.mode == PlayMode::Reverse;
+        self.pending = self.frame_events(self.current_frame);
+    }
+
+    pub fn frame_events(&self, frame: usize) -> AnimationEvents {
+        self.events.get(frame).copied().unwrap_or_default()
+    }
+
+    /// Time the current frame stays on screen.
+    pub fn current_duration(&self) -> Duration {
+        self.duration_of(self.current_frame)
+    }
+
+    fn duration_of(&self, frame: usize) -> Duration {
+        self.durations
+            .get(frame)
+            .copied()
+            .unwrap_or(self.frame_duration)
+    }
+
+    /// Ticks of one pass through a looping animation, `None` for modes that finish.
+    /// Zero length frames count as a tick, like in [`update`](Self::update).
+    fn cycle_ticks(&self) -> Option<u64> {
+        let ticks = |frames: core::ops::Range<usize>| -> u64 {
+            frames.map(|i| self.duration_of(i).as_ticks().max(1)).sum()
+        };
+        let last = self.frames.len().saturating_sub(1);
+        match self.mode {
+            PlayMode::Loop => Some(ticks(0..last + 1)),
+            // the frames between the ends show on the way there and back
+            PlayMode::PingPong => Some(ticks(0..last + 1) + ticks(1..last)),
+            PlayMode::Once | PlayMode::Reverse => None,
+        }
+    }
+
+    /// Advances by the time since the last update, skipping frames if needed.
+    /// Returns the events of every frame that started since the last update,
+    /// including the first frame after a reset.
+    pub fn update(&mut self, dt: Duration) -> AnimationEvents {
+        self.elapsed += dt;
+        // whole passes end where they started, so a long stall only costs one pass
+        if let Some(cycle) = self.cycle_ticks()
+            && self.elapsed.as_ticks() >= cycle
+        {
+            self.elapsed = Duration::from_ticks(self.elapsed.as_ticks() % cycle);
+            // and every frame started on the way
+            for &events in self.events.iter().take(self.frames.len()) {
+                self.pending |= events;
+            }
+        }
+        while !self.finished {
+            // zero length frames still show for a tick, so this always terminates
+            let duration = self.current_duration().max(Duration::from_ticks(1));
+            if self.elapsed < duration {
+                break;
+            }
+            self.elapsed -= duration;
+            self.step();
         }
+        core::mem::take(&mut self.pending)
     }
 
+    /// Moves to the next frame right away.
     pub fn advance(&mut self) {
-        self.current_frame += 1;
-        if self.current_frame >= self.frames.len() {
-            if self.looped {
-                self.current_frame = 0;
-            } else {
-                self.current_frame = self.frames.len() - 1;
-                self.finished = true;
+        self.elapsed = Duration::from_ticks(0);
+        self.step();
+    }
+
+    fn step(&mut self) {
+        let last = self.frames.len().saturating_sub(1);
+        match self.mode {
+            PlayMode::Once if self.current_frame >= last => self.finished = true,
+            PlayMode::Reverse if self.current_frame == 0 => self.finished = true,
+            PlayMode::Once => self.current_frame += 1,
+            PlayMode::Reverse => self.current_frame -= 1,
+            PlayMode::Loop => {
+                self.current_frame = if self.current_frame >= last {
+                    0
+                } else {
+                    self.current_frame + 1
+                };
+            }
+            PlayMode::PingPong if last == 0 => {}
+            PlayMode::PingPong => {
+                if self.current_frame >= last {
+                    self.backwards = true;
+                } else if self.current_frame == 0 {
+                    self.backwards = false;
+                }
+                if self.backwards {
+                    self.current_frame -= 1;
+                } else {
+                    self.current_frame += 1;
+                }
             }
         }
+        if self.finished {
+            self.elapsed = Duration::from_ticks(0);
+        } else {
+            self.pending |= self.frame_events(self.current_frame);
+        }
     }
 
     pub fn current_frame_sprite(&self) -> Option<Sprite<'a>> {
@@ -190,34 +894,189 @@ impl<'a> Animation<'a> {
     }
 }
 
-pub struct SpriteInstance<'a> {
-    pub animations: Vec<Animation<'a>, 4>,
+/// When a [`StateTransition`] applies
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TransitionTrigger {
+    /// The animation of `from` finished playing
+    Finished,
+    /// `to` was requested while `from` is playing
+    Requested,
+}
+
+/// Rule for moving between animation states, `from: None` applies to every state
+/// that has no rule of its own.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StateTransition<S> {
+    pub from: Option<S>,
+    pub to: S,
+    pub trigger: TransitionTrigger,
+}
+
+impl<S> StateTransition<S> {
+    /// Plays `to` once `from` finishes, e.g. attack returns to idle.
+    pub const fn on_finish(from: S, to: S) -> Self {
+        Self {
+            from: Some(from),
+            to,
+            trigger: TransitionTrigger::Finished,
+        }
+    }
+
+    /// Plays `to` once any state finishes.
+    pub const fn on_any_finish(to: S) -> Self {
+        Self {
+            from: None,
+            to,
+            trigger: TransitionTrigger::Finished,
+        }
+    }
+
+    /// Lets `to` cut `from` short, e.g. walk interrupts idle.
+    pub const fn interrupt(from: S, to: S) -> Self {
+        Self {
+            from: Some(from),
+            to,
+            trigger: TransitionTrigger::Requested,
+        }
+    }
+
+    /// Lets `to` cut any state short.
+    pub const fn interrupt_any(to: S) -> Self {
+        Self {
+            from: None,
+            to,
+            trigger: TransitionTrigger::Requested,
+        }
+    }
+}
+
+/// A positioned sprite playing one of up to `N` animations.
+/// Animations are addressed by index, or by state `S` when built with [`SpriteInstance::with_states`].
+pub struct SpriteInstance<'a, S = usize, const N: usize = 4> {
+    pub animations: Vec<Animation<'a>, N>,
     pub active_index: usize,
     pub position: Point,
+    states: Vec<S, N>,
+    transitions: &'a [StateTransition<S>],
 }
 
-impl<'a> SpriteInstance<'a> {
-    pub fn move_by(&mut self, dx: i32, dy: i32) {
-        self.position += Point::new(dx, dy);
-    }
-    pub fn update(&mut self, default_index: usize) {
-        self.advance();
-        if !self.current().looped && self.current().is_finished() {
-            self.trigger(default_index);
+impl<'a, const N: usize> SpriteInstance<'a, usize, N> {
+    /// Each animation is a state named by its index.
+    pub fn new(animations: Vec<Animation<'a>, N>, position: Point) -> Self {
+        let states = (0..animations.len()).collect();
+        Self {
+            animations,
+            active_index: 0,
+            position,
+            states,
+            transitions: &[],
         }
     }
-    pub fn new(animations: Vec<Animation<'a>, 4>, position: Point) -> Self {
+}
+
+impl<'a, S: Copy + Eq, const N: usize> SpriteInstance<'a, S, N> {
+    /// Starts in the first state.
+    pub fn with_states(states: Vec<(S, Animation<'a>), N>, position: Point) -> Self {
+        let (states, animations) = states.into_iter().unzip();
         Self {
             animations,
             active_index: 0,
             position,
+            states,
+            transitions: &[],
         }
     }
 
+    pub fn with_transitions(mut self, transitions: &'a [StateTransition<S>]) -> Self {
+        self.transitions = transitions;
+        self
+    }
+
+    pub fn move_by(&mut self, dx: i32, dy: i32) {
+        self.position += Point::new(dx, dy);
+    }
+
+    /// Plays the active animation for `dt`, following the finish transitions.
+    /// Returns the events of the frames that started, see [`Animation::update`].
+    pub fn update(&mut self, dt: Duration) -> AnimationEvents {
+        let mut events = self.current_mut().update(dt);
+        if self.current().is_finished()
+            && let Some(to) = self.transition(TransitionTrigger::Finished, None)
+        {
+            self.set_state(to);
+            events |= self.current_mut().update(Duration::from_ticks(0));
+        }
+        events
+    }
+
+    pub fn state(&self) -> S {
+        self.states[self.active_index]
+    }
+
+    /// Switches to `state` if a transition allows it while the current state plays.
+    /// Returns true if `state` is playing afterwards, requesting it again keeps it going.
+    pub fn request(&mut self, state: S) -> bool {
+        if self.state() == state {
+            return true;
+        }
+        self.transition(TransitionTrigger::Requested, Some(state))
+            .is_some_and(|to| self.set_state(to))
+    }
+
+    /// Restarts the animation of `state`, ignoring transitions.
+    /// Returns false if there is no such state.
+    pub fn set_state(&mut self, state: S) -> bool {
+        match self.states.iter().position(|&s| s == state) {
+            Some(index) => {
+                self.trigger(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Finds the rule leaving the current state, rules naming it win over catch all rules.
+    fn transition(&self, trigger: TransitionTrigger, to: Option<S>) -> Option<S> {
+        let current = self.state();
+        let matching = |from: Option<S>| {
+            self.transitions.iter().find(|rule| {
+                rule.trigger == trigger && rule.from == from && to.is_none_or(|to| rule.to == to)
+            })
+        };
+        matching(Some(current))
+            .or_else(|| matching(None))
+            .map(|rule| rule.to)
+    }
+
     pub fn current(&self) -> &Animation<'a> {
         &self.animations[self.active_index]
     }
 
+    /// Pixel perfect collision between the current frames, see [`Sprite::collides_with`].
+    pub fn collides_with<T, const M: usize>(
+        &self,
+        options: SpriteOptions,
+        other: &SpriteInstance<'_, T, M>,
+        other_options: SpriteOptions,
+    ) -> bool
+    where
+        T: Copy + Eq,
+    {
+        match (
+            self.current().current_frame_sprite(),
+            other.current().current_frame_sprite(),
+        ) {
+            (Some(sprite), Some(other_sprite)) => sprite.collides_with(
+                self.position,
+                options,
+                &other_sprite,
+                other.position,
+                other_options,
+            ),
+            _ => false,
+        }
+    }
+
     pub fn current_mut(&mut self) -> &mut Animation<'a> {
         &mut self.animations[self.active_index]
     }
@@ -229,9 +1088,7 @@ impl<'a> SpriteInstance<'a> {
     pub fn trigger(&mut self, index: usize) {
         if index < self.animations.len() {
             self.active_index = index;
-            let anim = self.current_mut();
-            anim.current_frame = 0;
-            anim.finished = false;
+            self.current_mut().reset();
         }
     }
 }
@@ -0,0 +1,344 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tilemaps for Kywy game engine
+//! A grid of tiles drawn from a [`SpriteSheet`], with an optional layer of [`TileFlags`]
+//! per cell for collision and other game rules. Maps live in world coordinates, draw them
+//! through a [`CameraView`](crate::engine::camera::CameraView) to scroll:
+//!
+//! ```ignore
+//! static TILES: [u16; 8 * 2] = [
+//!     0, 0, 0, 0, 0, 0, 0, 3,
+//!     1, 1, 2, 1, 1, 1, 1, 1,
+//! ];
+//! static FLAGS: [TileFlags; 8 * 2] = [...];
+//!
+//! let map = Tilemap::new(&tileset, 8, &TILES[..]).with_flags(&FLAGS[..]);
+//! let camera = Camera::default().with_bounds(map.bounds());
+//! map.draw(&mut camera.view(&mut display)).unwrap();
+//! if map.collides(player_box, TileFlags::SOLID) { ... }
+//! ```
+//!
+//! Tile 0 is empty, any other tile `n` is sprite `n - 1` of the sheet counting left to
+//! right, top to bottom. Tiles and flags can be `const` slices or owned buffers such as a
+//! `heapless::Vec` when the map is built or changed at runtime.
+
+use crate::engine::sprite::{SpriteOptions, SpriteSheet};
+use core::ops::{BitOr, BitOrAssign};
+use embedded_graphics::{
+    draw_target::DrawTarget,
+    geometry::{Point, Size},
+    pixelcolor::BinaryColor,
+    primitives::Rectangle,
+};
+
+/// Tile index that draws nothing
+pub const EMPTY_TILE: u16 = 0;
+
+/// Set of game defined flags for one map cell.
+///
+/// ```ignore
+/// const LADDER: TileFlags = TileFlags::flag(2);
+/// if map.flags_at(player.position).contains(LADDER) { ... }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct TileFlags(pub u8);
+
+impl TileFlags {
+    pub const NONE: Self = Self(0);
+    /// Blocks movement
+    pub const SOLID: Self = Self::flag(0);
+    /// Only blocks movement from above, for platforms that can be jumped through
+    pub const PLATFORM: Self = Self::flag(1);
+
+    /// The flag numbered `bit`, from 0 to 7. 0 and 1 are [`SOLID`](Self::SOLID)
+    /// and [`PLATFORM`](Self::PLATFORM), the rest are free for games to use.
+    pub const fn flag(bit: u8) -> Self {
+        Self(1 << bit)
+    }
+
+    /// Returns true if every flag in `flags` is set.
+    pub const fn contains(self, flags: Self) -> bool {
+        self.0 & flags.0 == flags.0
+    }
+
+    /// Returns true if any flag in `flags` is set.
+    pub const fn intersects(self, flags: Self) -> bool {
+        self.0 & flags.0 != 0
+    }
+
+    pub const fn union(self, flags: Self) -> Self {
+        Self(self.0 | flags.0)
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for TileFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        self.union(rhs)
+    }
+}
+
+impl BitOrAssign for TileFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = self.union(rhs);
+    }
+}
+
+/// Tiles and flags of a map layer without a tileset.
+/// Usually generated at build time from a Tiled map, see the `import` module.
+#[derive(Clone, Copy, Debug)]
+pub struct TilemapData<'a> {
+    pub columns: u32,
+    pub tiles: &'a [u16],
+    pub flags: &'a [TileFlags],
+}
+
+/// Custom property value set in the map editor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Property<'a> {
+    Bool(bool),
+    Int(i32),
+    Float(f32),
+    Str(&'a str),
+}
+
+/// Looks up a property by name in a generated property list.
+pub fn property<'a>(properties: &[(&str, Property<'a>)], name: &str) -> Option<Property<'a>> {
+    properties
+        .iter()
+        .find(|(key, _)| *key == name)
+        .map(|&(_, value)| value)
+}
+
+/// Object placed in the map editor, like a spawn point or a trigger area.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapObject<'a> {
+    pub name: &'a str,
+    /// Class (or type) given in the editor
+    pub kind: &'a str,
+    /// Top left corner relative to the map
+    pub position: Point,
+    /// Zero for point objects
+    pub size: Size,
+    /// Tile shown by tile objects, [`EMPTY_TILE`] for the rest
+    pub tile: u16,
+    pub properties: &'a [(&'a str, Property<'a>)],
+}
+
+impl<'a> MapObject<'a> {
+    pub fn area(&self) -> Rectangle {
+        Rectangle::new(self.position, self.size)
+    }
+
+    pub fn property(&self, name: &str) -> Option<Property<'a>> {
+        property(self.properties, name)
+    }
+}
+
+/// Grid of `columns` tiles per row, stored row by row in `T`.
+pub struct Tilemap<'a, T = &'a [u16], F = &'a [TileFlags]> {
+    tileset: &'a SpriteSheet<'a>,
+    columns: u32,
+    tiles: T,
+    flags: Option<F>,
+    /// World coordinate of the top left corner of the map
+    pub position: Point,
+}
+
+impl<'a, T, F> Tilemap<'a, T, F>
+where
+    T: AsRef<[u16]>,
+    F: AsRef<[TileFlags]>,
+{
+    /// Creates a map without flags at the world origin. The number of rows follows from
+    /// the number of tiles, a partial last row is ignored.
+    pub fn new(tileset: &'a SpriteSheet<'a>, columns: u32, tiles: T) -> Self {
+        Self {
+            tileset,
+            columns: columns.max(1),
+            tiles,
+            flags: None,
+            position: Point::zero(),
+        }
+    }
+
+    /// Adds a flag layer with one entry per tile, missing entries have no flags.
+    pub fn with_flags(mut self, flags: F) -> Self {
+        self.flags = Some(flags);
+        self
+    }
+
+    pub fn with_position(mut self, position: Point) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn tileset(&self) -> &'a SpriteSheet<'a> {
+        self.tileset
+    }
+
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.tiles.as_ref().len() as u32 / self.columns
+    }
+
+    pub fn tile_size(&self) -> Size {
+        self.tileset.sprite_size()
+    }
+
+    /// Area the map covers in world coordinates, e.g. for [`Camera::with_bounds`].
+    ///
+    /// [`Camera::with_bounds`]: crate::engine::camera::Camera::with_bounds
+    pub fn bounds(&self) -> Rectangle {
+        let tile = self.tile_size();
+        Rectangle::new(
+            self.position,
+            Size::new(self.columns * tile.width, self.rows() * tile.height),
+        )
+    }
+
+    fn index(&self, column: u32, row: u32) -> Option<usize> {
+        (column < self.columns && row < self.rows())
+            .then_some(row as usize * self.columns as usize + column as usize)
+    }
+
+    /// Tile at a cell, `None` outside of the map.
+    pub fn tile(&self, column: u32, row: u32) -> Option<u16> {
+        self.index(column, row).map(|i| self.tiles.as_ref()[i])
+    }
+
+    /// Flags of a cell, none outside of the map or without a flag layer.
+    pub fn flags(&self, column: u32, row: u32) -> TileFlags {
+        let flags = self.flags.as_ref().map_or(&[][..], |flags| flags.as_ref());
+        self.index(column, row)
+            .and_then(|i| flags.get(i).copied())
+            .unwrap_or(TileFlags::NONE)
+    }
+
+    /// Cell containing a world coordinate, `None` outside of the map.
+    pub fn cell_at(&self, world: Point) -> Option<(u32, u32)> {
+        let local = world - self.position;
+        let tile = self.tile_size();
+        if local.x < 0 || local.y < 0 || tile.width == 0 || tile.height == 0 {
+            return None;
+        }
+        let cell = (local.x as u32 / tile.width, local.y as u32 / tile.height);
+        self.index(cell.0, cell.1).map(|_| cell)
+    }
+
+    pub fn tile_at(&self, world: Point) -> Option<u16> {
+        self.cell_at(world)
+            .and_then(|(column, row)| self.tile(column, row))
+    }
+
+    pub fn flags_at(&self, world: Point) -> TileFlags {
+        self.cell_at(world)
+            .map_or(TileFlags::NONE, |(column, row)| self.flags(column, row))
+    }
+
+    /// World area of a cell.
+    pub fn cell_area(&self, column: u32, row: u32) -> Rectangle {
+        let tile = self.tile_size();
+        Rectangle::new(
+            self.position + Point::new((column * tile.width) as i32, (row * tile.height) as i32),
+            tile,
+        )
+    }
+
+    /// Cells that overlap a world area, row by row.
+    pub fn cells_in(&self, area: Rectangle) -> impl Iterator<Item = (u32, u32)> {
+        let tile = self.tile_size();
+        let area = area.intersection(&self.bounds());
+        let (columns, rows) = match area.bottom_right() {
+            Some(bottom_right) if tile.width > 0 && tile.height > 0 => {
+                let start = area.top_left - self.position;
+                let end = bottom_right - self.position;
+                (
+                    start.x as u32 / tile.width..end.x as u32 / tile.width + 1,
+                    start.y as u32 / tile.height..end.y as u32 / tile.height + 1,
+                )
+            }
+            _ => (0..0, 0..0),
+        };
+        rows.flat_map(move |row| columns.clone().map(move |column| (column, row)))
+    }
+
+    /// Returns true if any cell overlapping a world area has one of `flags`.
+    pub fn collides(&self, area: Rectangle, flags: TileFlags) -> bool {
+        self.cells_in(area)
+            .any(|(column, row)| self.flags(column, row).intersects(flags))
+    }
+
+    /// Draws the tiles that overlap the target's bounding box, with the target in world
+    /// coordinates. Empty tiles and tiles past the end of the sheet leave the target alone.
+    pub fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let sheet_columns = self.tileset.sprite_count().width.max(1);
+        for (column, row) in self.cells_in(target.bounding_box()) {
+            let tile = self.tile(column, row).unwrap_or(EMPTY_TILE);
+            let Some(index) = tile.checked_sub(1).map(u32::from) else {
+                continue;
+            };
+            if let Some(sprite) = self
+                .tileset
+                .sprite(index % sheet_columns, index / sheet_columns)
+            {
+                let pos = self.cell_area(column, row).top_left;
+                sprite.draw(target, pos, SpriteOptions::default())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Tilemap<'a> {
+    pub fn from_data(tileset: &'a SpriteSheet<'a>, data: &TilemapData<'a>) -> Self {
+        Self::new(tileset, data.columns, data.tiles).with_flags(data.flags)
+    }
+}
+
+impl<T, F> Tilemap<'_, T, F>
+where
+    T: AsRef<[u16]> + AsMut<[u16]>,
+    F: AsRef<[TileFlags]>,
+{
+    /// Changes a tile, returns false outside of the map.
+    pub fn set_tile(&mut self, column: u32, row: u32, tile: u16) -> bool {
+        match self.index(column, row) {
+            Some(i) => {
+                self.tiles.as_mut()[i] = tile;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<T, F> Tilemap<'_, T, F>
+where
+    T: AsRef<[u16]>,
+    F: AsRef<[TileFlags]> + AsMut<[TileFlags]>,
+{
+    /// Changes the flags of a cell, returns false outside of the map or the flag layer.
+    pub fn set_flags(&mut self, column: u32, row: u32, flags: TileFlags) -> bool {
+        let index = self.index(column, row);
+        match (index, self.flags.as_mut()) {
+            (Some(i), Some(layer)) if i < layer.as_ref().len() => {
+                layer.as_mut()[i] = flags;
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,209 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Screen transitions for Kywy game engine
+//! Animates from one framebuffer to another, e.g. from a splash screen to gameplay.
+//! [`mix`] renders a single step and runs anywhere, [`play`] drives the display.
+
+use crate::framebuffer::Framebuffer;
+use embedded_graphics::pixelcolor::BinaryColor;
+
+#[cfg(target_os = "none")]
+use crate::display::{DisplayError, KywyDisplay};
+#[cfg(target_os = "none")]
+use embassy_time::{Duration, Instant};
+#[cfg(target_os = "none")]
+use embedded_hal_async::spi::SpiDevice;
+
+/// Progress value at which a transition shows only the new screen
+pub const PROGRESS_END: u8 = 255;
+
+/// Side of the screen an effect moves towards
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Transition {
+    /// The new screen is uncovered by an edge moving in the direction
+    Wipe(Direction),
+    /// The new screen pushes the old one out in the direction
+    Slide(Direction),
+    /// Pixels switch over in a random looking but fixed order
+    Dissolve,
+    /// Every other 8x8 square fills in first, then the rest
+    Checkerboard,
+    /// The new screen appears in a growing circle from the center
+    Iris,
+}
+
+const CHECKER_SIZE: usize = 8;
+
+/// Renders `transition` at `progress` (0 to [`PROGRESS_END`]) into `out`.
+/// Coordinates are in drawing orientation, all three buffers should use the same rotation.
+pub fn mix(
+    from: &Framebuffer,
+    to: &Framebuffer,
+    out: &mut Framebuffer,
+    transition: Transition,
+    progress: u8,
+) {
+    match progress {
+        0 => return out.copy_from(from),
+        PROGRESS_END => return out.copy_from(to),
+        _ => {}
+    }
+
+    let (width, height) = (out.width(), out.height());
+    for y in 0..height {
+        for x in 0..width {
+            let color = match source(transition, progress, x, y, width, height) {
+                Source::From(x, y) => from.get_pixel(x, y),
+                Source::To(x, y) => to.get_pixel(x, y),
+            };
+            out.set_pixel(x, y, color.unwrap_or(BinaryColor::Off));
+        }
+    }
+}
+
+/// Plays `transition` on the display over `duration`, as fast as the panel allows.
+/// The display shows `to` once it returns.
+#[cfg(target_os = "none")]
+pub async fn play<SPI>(
+    display: &mut KywyDisplay<'_, SPI>,
+    from: &Framebuffer,
+    to: &Framebuffer,
+    transition: Transition,
+    duration: Duration,
+) -> Result<(), DisplayError<SPI::Error>>
+where
+    SPI: SpiDevice,
+{
+    let start = Instant::now();
+    loop {
+        let elapsed = start.elapsed().as_ticks();
+        let progress = match duration.as_ticks() {
+            0 => PROGRESS_END,
+            total => (elapsed.min(total) * PROGRESS_END as u64 / total) as u8,
+        };
+
+        mix(from, to, display.framebuffer_mut(), transition, progress);
+        display.write_display().await?;
+
+        if progress == PROGRESS_END {
+            return Ok(());
+        }
+    }
+}
+
+enum Source {
+    From(usize, usize),
+    To(usize, usize),
+}
+
+/// Picks which screen, and where on it, the pixel at `x`, `y` comes from.
+fn source(
+    transition: Transition,
+    progress: u8,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+) -> Source {
+    let scale = |size: usize| size * progress as usize / PROGRESS_END as usize;
+
+    match transition {
+        Transition::Wipe(direction) => {
+            let switched = match direction {
+                Direction::Left => x >= width - scale(width),
+                Direction::Right => x < scale(width),
+                Direction::Up => y >= height - scale(height),
+                Direction::Down => y < scale(height),
+            };
+            if switched {
+                Source::To(x, y)
+            } else {
+                Source::From(x, y)
+            }
+        }
+        Transition::Slide(direction) => match direction {
+            Direction::Left => {
+                let x = x + scale(width);
+                if x < width {
+                    Source::From(x, y)
+                } else {
+                    Source::To(x - width, y)
+                }
+            }
+            Direction::Right => {
+                let offset = scale(width);
+                if x >= offset {
+                    Source::From(x - offset, y)
+                } else {
+                    Source::To(x + width - offset, y)
+                }
+            }
+            Direction::Up => {
+                let y = y + scale(height);
+                if y < height {
+                    Source::From(x, y)
+                } else {
+                    Source::To(x, y - height)
+                }
+            }
+            Direction::Down => {
+                let offset = scale(height);
+                if y >= offset {
+                    Source::From(x, y - offset)
+                } else {
+                    Source::To(x, y + height - offset)
+                }
+            }
+        },
+        Transition::Dissolve => {
+            if noise(x, y) < progress {
+                Source::To(x, y)
+            } else {
+                Source::From(x, y)
+            }
+        }
+        Transition::Checkerboard => {
+            // the first half fills one color of squares top to bottom, the second half the other
+            let parity = (x / CHECKER_SIZE + y / CHECKER_SIZE) % 2;
+            let square = (progress as usize * 2)
+                .saturating_sub(parity * PROGRESS_END as usize)
+                .min(PROGRESS_END as usize);
+            let rows = CHECKER_SIZE * square / PROGRESS_END as usize;
+            if y % CHECKER_SIZE < rows {
+                Source::To(x, y)
+            } else {
+                Source::From(x, y)
+            }
+        }
+        Transition::Iris => {
+            let dx = x.abs_diff(width / 2) as u64;
+            let dy = y.abs_diff(height / 2) as u64;
+            // compare squared distances, scaled by PROGRESS_END squared, to avoid a square root
+            let corner = ((width / 2).pow(2) + (height / 2).pow(2)) as u64;
+            let end = PROGRESS_END as u64;
+            if (dx * dx + dy * dy) * end * end <= corner * (progress as u64).pow(2) {
+                Source::To(x, y)
+            } else {
+                Source::From(x, y)
+            }
+        }
+    }
+}
+
+/// Cheap integer hash giving every pixel a fixed switch over point.
+fn noise(x: usize, y: usize) -> u8 {
+    let mut h = (x as u32).wrapping_mul(0x9E37_79B1) ^ (y as u32).wrapping_mul(0x85EB_CA77);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2C1B_3C6D);
+    h ^= h >> 12;
+    (h >> 24) as u8
+}
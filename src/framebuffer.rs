@@ -0,0 +1,434 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Hardware independent 1bpp framebuffer with the Kywy screen geometry.
+//! Compatable with embedded-graphics DrawTarget, so rendering code can run on
+//! a host machine without any RP2040 peripherals.
+//!
+//! Pixels are packed 8 per byte, least significant bit first, one row after the
+//! other. Every row that changes is marked dirty so the display only has to send
+//! those rows to the panel.
+//!
+//! Drawing coordinates can be rotated and mirrored with [`DisplayRotation`] and
+//! [`Framebuffer::set_mirror`], the raw buffer always stays in panel orientation.
+
+use bitvec::BitArr;
+use core::convert::Infallible;
+use core::ops::Range;
+use embedded_graphics::{
+    Pixel,
+    draw_target::DrawTarget,
+    geometry::Point,
+    image::GetPixel,
+    pixelcolor::BinaryColor,
+    prelude::{Dimensions, OriginDimensions, Size},
+    primitives::{PointsIter, Rectangle},
+};
+
+pub const WIDTH: usize = 144;
+pub const HEIGHT: usize = 168;
+pub(crate) const BYTES_PER_LINE: usize = WIDTH / 8;
+pub(crate) const TOTAL_BUFFER_SIZE: usize = WIDTH * HEIGHT / 8;
+
+pub(crate) type LineMask = BitArr!(for HEIGHT, in u32);
+
+pub(crate) fn all_lines() -> LineMask {
+    // the backing array is rounded up to whole words, only the first HEIGHT bits are lines
+    let mut mask = LineMask::ZERO;
+    mask[..HEIGHT].fill(true);
+    mask
+}
+
+/// Clockwise rotation of the drawing coordinates relative to the panel.
+/// `Rotate90` and `Rotate270` give a 168x144 landscape screen.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DisplayRotation {
+    #[default]
+    Rotate0,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+#[derive(Clone)]
+pub struct Framebuffer {
+    buffer: [u8; TOTAL_BUFFER_SIZE],
+    dirty: LineMask,
+    rotation: DisplayRotation,
+    mirror_x: bool,
+    mirror_y: bool,
+}
+
+impl Default for Framebuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Framebuffer {
+    /// Creates a buffer with every pixel Off and every line marked dirty.
+    pub fn new() -> Self {
+        Self {
+            buffer: [0x00; TOTAL_BUFFER_SIZE],
+            dirty: all_lines(),
+            rotation: DisplayRotation::Rotate0,
+            mirror_x: false,
+            mirror_y: false,
+        }
+    }
+
+    /// Sets the rotation used for all following drawing, existing contents are kept as is.
+    pub fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.rotation = rotation;
+    }
+
+    pub fn rotation(&self) -> DisplayRotation {
+        self.rotation
+    }
+
+    /// Mirrors drawing horizontally and/or vertically, applied before the rotation.
+    pub fn set_mirror(&mut self, horizontal: bool, vertical: bool) {
+        self.mirror_x = horizontal;
+        self.mirror_y = vertical;
+    }
+
+    pub fn mirror(&self) -> (bool, bool) {
+        (self.mirror_x, self.mirror_y)
+    }
+
+    /// Width in drawing coordinates, 168 when rotated to landscape.
+    pub fn width(&self) -> usize {
+        match self.rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => WIDTH,
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => HEIGHT,
+        }
+    }
+
+    /// Height in drawing coordinates, 144 when rotated to landscape.
+    pub fn height(&self) -> usize {
+        match self.rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => HEIGHT,
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => WIDTH,
+        }
+    }
+
+    fn is_transformed(&self) -> bool {
+        self.rotation != DisplayRotation::Rotate0 || self.mirror_x || self.mirror_y
+    }
+
+    /// Maps drawing coordinates to panel coordinates, None if off screen.
+    fn to_panel(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        let (width, height) = (self.width(), self.height());
+        if x >= width || y >= height {
+            return None;
+        }
+
+        let x = if self.mirror_x { width - 1 - x } else { x };
+        let y = if self.mirror_y { height - 1 - y } else { y };
+        Some(match self.rotation {
+            DisplayRotation::Rotate0 => (x, y),
+            DisplayRotation::Rotate90 => (WIDTH - 1 - y, x),
+            DisplayRotation::Rotate180 => (WIDTH - 1 - x, HEIGHT - 1 - y),
+            DisplayRotation::Rotate270 => (y, HEIGHT - 1 - x),
+        })
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<BinaryColor> {
+        let (x, y) = self.to_panel(x, y)?;
+        self.get_raw_pixel(x, y)
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: BinaryColor) {
+        if let Some((x, y)) = self.to_panel(x, y) {
+            self.set_raw_pixel(x, y, color);
+        }
+    }
+
+    /// Reads a pixel in panel orientation, ignoring rotation and mirroring.
+    pub fn get_raw_pixel(&self, x: usize, y: usize) -> Option<BinaryColor> {
+        if x >= WIDTH || y >= HEIGHT {
+            return None;
+        }
+
+        let index = (y * BYTES_PER_LINE) + (x / 8);
+        let bit = x % 8;
+        Some(BinaryColor::from(self.buffer[index] & (1 << bit) != 0))
+    }
+
+    /// Sets a pixel in panel orientation, ignoring rotation and mirroring.
+    pub fn set_raw_pixel(&mut self, x: usize, y: usize, color: BinaryColor) {
+        if x >= WIDTH || y >= HEIGHT {
+            return;
+        }
+
+        let index = (y * BYTES_PER_LINE) + (x / 8);
+        let bit = x % 8;
+
+        let old = self.buffer[index];
+        let new = if color.is_on() {
+            old | (1 << bit)
+        } else {
+            old & !(1 << bit)
+        };
+
+        if new != old {
+            self.buffer[index] = new;
+            self.dirty.set(y, true);
+        }
+    }
+
+    /// Sets every pixel to `color`, only lines that change are marked dirty.
+    pub fn fill(&mut self, color: BinaryColor) {
+        let fill_value = if color.is_on() { 0xFF } else { 0x00 };
+        for (line, bytes) in self.buffer.chunks_exact_mut(BYTES_PER_LINE).enumerate() {
+            if bytes.iter().any(|&b| b != fill_value) {
+                bytes.fill(fill_value);
+                self.dirty.set(line, true);
+            }
+        }
+    }
+
+    /// Sets pixels `x0..x1` of row `y` in one pass over whole bytes.
+    /// Callers must clip the span to the buffer first.
+    fn fill_span(&mut self, y: usize, x0: usize, x1: usize, color: BinaryColor) {
+        let row = &mut self.buffer[y * BYTES_PER_LINE..(y + 1) * BYTES_PER_LINE];
+        let mut changed = false;
+
+        let mut x = x0;
+        while x < x1 {
+            let byte = x / 8;
+            let start = x % 8;
+            let end = (x1 - byte * 8).min(8);
+            let mask = (((1u16 << end) - 1) & !((1u16 << start) - 1)) as u8;
+
+            let old = row[byte];
+            let new = if color.is_on() {
+                old | mask
+            } else {
+                old & !mask
+            };
+            changed |= new != old;
+            row[byte] = new;
+
+            x = byte * 8 + end;
+        }
+
+        if changed {
+            self.dirty.set(y, true);
+        }
+    }
+
+    /// Copies `area` of `src` into this buffer with its top left corner at `dest`.
+    /// Pixels falling outside either buffer are skipped.
+    pub fn blit(&mut self, src: &Framebuffer, area: Rectangle, dest: Point) {
+        let area = area.intersection(&src.bounding_box());
+        let Some(bottom_right) = area.bottom_right() else {
+            return;
+        };
+
+        for sy in area.top_left.y..=bottom_right.y {
+            for sx in area.top_left.x..=bottom_right.x {
+                let dx = dest.x + sx - area.top_left.x;
+                let dy = dest.y + sy - area.top_left.y;
+                if dx < 0 || dy < 0 {
+                    continue;
+                }
+                if let Some(color) = src.get_pixel(sx as usize, sy as usize) {
+                    self.set_pixel(dx as usize, dy as usize, color);
+                }
+            }
+        }
+    }
+
+    /// Replaces the whole contents with `src`, marking only lines that differ.
+    pub fn copy_from(&mut self, src: &Framebuffer) {
+        let lines = self
+            .buffer
+            .chunks_exact_mut(BYTES_PER_LINE)
+            .zip(src.buffer.chunks_exact(BYTES_PER_LINE));
+        for (line, (dst, src)) in lines.enumerate() {
+            if dst != src {
+                dst.copy_from_slice(src);
+                self.dirty.set(line, true);
+            }
+        }
+    }
+
+    /// Raw packed bytes of one row.
+    pub fn line(&self, y: usize) -> &[u8] {
+        &self.buffer[y * BYTES_PER_LINE..(y + 1) * BYTES_PER_LINE]
+    }
+
+    /// Raw packed bytes of the whole buffer.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Returns true if any line changed since the dirty flags were last cleared.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.any()
+    }
+
+    pub fn is_line_dirty(&self, y: usize) -> bool {
+        y < HEIGHT && self.dirty[y]
+    }
+
+    pub fn mark_dirty(&mut self, lines: Range<usize>) {
+        // a reversed range is empty rather than a panic
+        let end = lines.end.min(HEIGHT);
+        let start = lines.start.min(end);
+        self.dirty[start..end].fill(true);
+    }
+
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty = all_lines();
+    }
+
+    /// Forgets which lines changed, e.g. after sending the buffer some other way.
+    pub fn mark_clean(&mut self) {
+        self.dirty = LineMask::ZERO;
+    }
+
+    // only the display and the simulator transmit lines
+    #[cfg(any(target_os = "none", feature = "std"))]
+    pub(crate) fn dirty_lines(&self) -> LineMask {
+        self.dirty
+    }
+
+    #[cfg(any(target_os = "none", feature = "std"))]
+    pub(crate) fn clear_dirty(&mut self, lines: LineMask) {
+        self.dirty &= !lines;
+    }
+}
+
+impl OriginDimensions for Framebuffer {
+    fn size(&self) -> Size {
+        Size::new(self.width() as u32, self.height() as u32)
+    }
+}
+
+impl GetPixel for Framebuffer {
+    type Color = BinaryColor;
+
+    fn pixel(&self, p: Point) -> Option<Self::Color> {
+        if p.x < 0 || p.y < 0 {
+            return None;
+        }
+        self.get_pixel(p.x as usize, p.y as usize)
+    }
+}
+
+impl DrawTarget for Framebuffer {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(coord, color) in pixels {
+            if coord.x >= 0 && coord.y >= 0 {
+                self.set_pixel(coord.x as usize, coord.y as usize, color);
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        if self.is_transformed() {
+            let pixels = area.points().zip(colors).map(|(p, c)| Pixel(p, c));
+            return self.draw_iter(pixels);
+        }
+
+        let drawable = area.intersection(&self.bounding_box());
+        let (Some(area_end), Some(drawable_end)) = (area.bottom_right(), drawable.bottom_right())
+        else {
+            return Ok(());
+        };
+
+        // colors cover the whole area row by row, the clipped parts are consumed and skipped
+        let mut colors = colors.into_iter();
+        for y in area.top_left.y..=area_end.y {
+            let row_visible = y >= drawable.top_left.y && y <= drawable_end.y;
+            // only used for visible rows, which are never negative
+            let row = if row_visible {
+                y as usize * BYTES_PER_LINE
+            } else {
+                0
+            };
+            let mut changed = false;
+
+            // bits are collected per byte and written once the row moves on to the next byte
+            let mut byte = usize::MAX;
+            let (mut mask, mut bits) = (0u8, 0u8);
+            let mut flush = |byte: usize, mask: u8, bits: u8| {
+                if mask != 0 {
+                    let old = self.buffer[row + byte];
+                    let new = (old & !mask) | bits;
+                    changed |= new != old;
+                    self.buffer[row + byte] = new;
+                }
+            };
+
+            for x in area.top_left.x..=area_end.x {
+                let Some(color) = colors.next() else {
+                    flush(byte, mask, bits);
+                    if changed {
+                        self.dirty.set(y as usize, true);
+                    }
+                    return Ok(());
+                };
+                if !row_visible || x < drawable.top_left.x || x > drawable_end.x {
+                    continue;
+                }
+
+                let x = x as usize;
+                if x / 8 != byte {
+                    flush(byte, mask, bits);
+                    (byte, mask, bits) = (x / 8, 0, 0);
+                }
+                let bit = 1 << (x % 8);
+                mask |= bit;
+                if color.is_on() {
+                    bits |= bit;
+                }
+            }
+            flush(byte, mask, bits);
+
+            if changed {
+                self.dirty.set(y as usize, true);
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.bounding_box());
+        let Some(bottom_right) = area.bottom_right() else {
+            return Ok(());
+        };
+
+        // a rotated or mirrored rectangle is still a rectangle on the panel
+        let corner_a = self.to_panel(area.top_left.x as usize, area.top_left.y as usize);
+        let corner_b = self.to_panel(bottom_right.x as usize, bottom_right.y as usize);
+        let (Some((ax, ay)), Some((bx, by))) = (corner_a, corner_b) else {
+            return Ok(());
+        };
+
+        let x0 = ax.min(bx);
+        let x1 = ax.max(bx) + 1;
+        for y in ay.min(by)..=ay.max(by) {
+            self.fill_span(y, x0, x1, color);
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.fill(color);
+        Ok(())
+    }
+}
@@ -0,0 +1,622 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Build time import of sprite sheet metadata, requires the `import` feature.
+//!
+//! Reads a TOML descriptor or an Aseprite JSON export next to a BMP sprite sheet and
+//! generates a module with the frame layout, named animations and frame durations as
+//! `const` data, so frames do not have to be listed by hand. Run it from the game's
+//! build script, with kywy as a build dependency:
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     kywy::import::build_sprites("assets/electric.toml").unwrap();
+//! }
+//!
+//! // main.rs
+//! include!(concat!(env!("OUT_DIR"), "/electric.rs"));
+//!
+//! let sheet = electric::sheet().unwrap();
+//! let idle = Animation::from_data(&sheet, &electric::IDLE);
+//! ```
+//!
+//! A TOML descriptor lists frames by their column and row in the sheet:
+//!
+//! ```toml
+//! image = "electric.bmp"
+//! sprite_width = 64
+//! sprite_height = 64
+//! frame_duration = 100 # milliseconds, for animations without durations
+//!
+//! [[animation]]
+//! name = "idle"
+//! frames = [[0, 0], [1, 0], [2, 0]]
+//! mode = "ping_pong" # once, loop (default), ping_pong or reverse
+//! durations = [150, 100, 150]
+//! ```
+//!
+//! Aseprite exports (Export Sprite Sheet with JSON data, as hash or array) are read
+//! from their frame rectangles, durations and tags, each tag becomes an animation.
+//! The sheet has to be saved as a 1bpp BMP with all frames the same size on a grid.
+//!
+//! Tiled maps (`.tmx`, or `.tmj`/`.json` exports) are converted by [`build_map`] into a
+//! module with a [`TilemapData`](crate::engine::tilemap::TilemapData) per tile layer,
+//! the objects of every object layer and the custom properties of the map:
+//!
+//! ```ignore
+//! // build.rs
+//! kywy::import::build_map("assets/level1.tmx").unwrap();
+//!
+//! // main.rs
+//! include!(concat!(env!("OUT_DIR"), "/level1.rs"));
+//!
+//! let tileset = level1::tileset().unwrap();
+//! let ground = Tilemap::from_data(&tileset, &level1::GROUND);
+//! let spawn = level1::SPAWNS.iter().find(|object| object.name == "player");
+//! ```
+//!
+//! The map has to be orthogonal and finite, with one tileset made from a single 1bpp BMP
+//! without margin or spacing. Tiles with a `solid` or `platform` bool property, or a
+//! `flags` int property, set those [`TileFlags`](crate::engine::tilemap::TileFlags) on
+//! every cell they are placed in. Tile layers must be CSV or uncompressed Base64, and
+//! a tile past the end of the tileset image fails the build.
+
+mod tiled;
+
+use crate::engine::sprite::ParseError;
+use serde::Deserialize;
+use std::fmt::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+use tinybmp::RawBmp;
+
+/// Names the generated sprite module already uses
+const RESERVED: [&str; 5] = ["IMAGE", "MASK", "SPRITE_SIZE", "FRAMES", "ANIMATIONS"];
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(PathBuf, io::Error),
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+    Xml(roxmltree::Error),
+    Bmp(PathBuf, ParseError),
+    /// The descriptor parsed but does not describe a usable sheet
+    Invalid(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            ImportError::Json(err) => write!(f, "invalid JSON: {err}"),
+            ImportError::Toml(err) => write!(f, "invalid sprite descriptor: {err}"),
+            ImportError::Xml(err) => write!(f, "invalid Tiled XML: {err}"),
+            ImportError::Bmp(path, err) => {
+                write!(f, "{}: not a BMP image ({err:?})", path.display())
+            }
+            ImportError::Invalid(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// Generates the sprite module for `descriptor` into `OUT_DIR`, named after the file.
+/// Tells cargo to rerun the build script when the descriptor or images change.
+pub fn build_sprites(descriptor: impl AsRef<Path>) -> Result<PathBuf, ImportError> {
+    let descriptor = descriptor.as_ref();
+    let sheet = load(descriptor)?;
+    let out_dir = env::var_os("OUT_DIR").ok_or_else(|| {
+        ImportError::Invalid("OUT_DIR is not set, call this from build.rs".into())
+    })?;
+    let out = PathBuf::from(out_dir).join(format!("{}.rs", sheet.name));
+    fs::write(&out, sheet.to_source()).map_err(|err| ImportError::Io(out.clone(), err))?;
+
+    println!("cargo:rerun-if-changed={}", descriptor.display());
+    for image in [Some(&sheet.image), sheet.mask.as_ref()]
+        .into_iter()
+        .flatten()
+    {
+        println!("cargo:rerun-if-changed={}", image.display());
+    }
+    Ok(out)
+}
+
+/// Returns the generated sprite module for `descriptor` as Rust source.
+/// Files ending in `.json` are read as Aseprite exports, anything else as TOML.
+pub fn generate(descriptor: impl AsRef<Path>) -> Result<String, ImportError> {
+    Ok(load(descriptor.as_ref())?.to_source())
+}
+
+/// Generates the tilemap module for a Tiled map into `OUT_DIR`, named after the file.
+/// Tells cargo to rerun the build script when the map, tileset or tileset image change.
+pub fn build_map(map: impl AsRef<Path>) -> Result<PathBuf, ImportError> {
+    let path = map.as_ref();
+    let map = tiled::load(path)?;
+    let out_dir = env::var_os("OUT_DIR").ok_or_else(|| {
+        ImportError::Invalid("OUT_DIR is not set, call this from build.rs".into())
+    })?;
+    let out = PathBuf::from(out_dir).join(format!("{}.rs", map.name));
+    fs::write(&out, map.to_source()).map_err(|err| ImportError::Io(out.clone(), err))?;
+
+    println!("cargo:rerun-if-changed={}", path.display());
+    for file in [map.tileset.source.as_ref(), Some(&map.tileset.image)]
+        .into_iter()
+        .flatten()
+    {
+        println!("cargo:rerun-if-changed={}", file.display());
+    }
+    Ok(out)
+}
+
+/// Returns the generated tilemap module for a Tiled map as Rust source.
+/// Files ending in `.tmx` are read as XML, anything else as JSON.
+pub fn generate_map(map: impl AsRef<Path>) -> Result<String, ImportError> {
+    Ok(tiled::load(map.as_ref())?.to_source())
+}
+
+/// Sheet layout in the form the generated module uses.
+struct Sheet {
+    name: String,
+    source: PathBuf,
+    image: PathBuf,
+    mask: Option<PathBuf>,
+    sprite_size: (u32, u32),
+    /// (x, y) of every frame rectangle in pixels
+    frames: Vec<(u32, u32)>,
+    animations: Vec<Animation>,
+}
+
+struct Animation {
+    name: String,
+    /// Column and row of each frame
+    frames: Vec<(u32, u32)>,
+    /// Milliseconds, one per frame
+    durations: Vec<u64>,
+    mode: Mode,
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Mode {
+    Once,
+    #[default]
+    Loop,
+    PingPong,
+    Reverse,
+}
+
+fn load(descriptor: &Path) -> Result<Sheet, ImportError> {
+    let text =
+        fs::read_to_string(descriptor).map_err(|err| ImportError::Io(descriptor.into(), err))?;
+    let dir = descriptor.parent().unwrap_or(Path::new(""));
+    let name = module_name(descriptor)?;
+
+    let sheet = match descriptor.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => aseprite(&text, dir, name)?,
+        _ => toml_descriptor(&text, dir, name)?,
+    };
+    sheet.validate()?;
+    Ok(Sheet {
+        source: descriptor.into(),
+        ..sheet
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Descriptor {
+    image: PathBuf,
+    mask: Option<PathBuf>,
+    sprite_width: u32,
+    sprite_height: u32,
+    #[serde(default = "default_frame_duration")]
+    frame_duration: u64,
+    #[serde(default, rename = "animation")]
+    animations: Vec<AnimationDescriptor>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AnimationDescriptor {
+    name: String,
+    frames: Vec<(u32, u32)>,
+    #[serde(default)]
+    durations: Vec<u64>,
+    #[serde(default)]
+    mode: Mode,
+}
+
+fn default_frame_duration() -> u64 {
+    100
+}
+
+fn toml_descriptor(text: &str, dir: &Path, name: String) -> Result<Sheet, ImportError> {
+    let descriptor: Descriptor = toml::from_str(text).map_err(ImportError::Toml)?;
+    let sprite_size = (descriptor.sprite_width, descriptor.sprite_height);
+    let image = dir.join(&descriptor.image);
+    let (columns, rows) = grid_size(&image, sprite_size)?;
+
+    let frames = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .map(|(column, row)| (column * sprite_size.0, row * sprite_size.1))
+        .collect();
+
+    let animations = descriptor
+        .animations
+        .into_iter()
+        .map(|animation| {
+            let durations = match animation.durations.len() {
+                0 => vec![descriptor.frame_duration; animation.frames.len()],
+                n if n == animation.frames.len() => animation.durations,
+                n => {
+                    return Err(ImportError::Invalid(format!(
+                        "animation `{}` has {} frames but {n} durations",
+                        animation.name,
+                        animation.frames.len()
+                    )));
+                }
+            };
+            Ok(Animation {
+                name: animation.name,
+                frames: animation.frames,
+                durations,
+                mode: animation.mode,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Sheet {
+        name,
+        source: PathBuf::new(),
+        image,
+        mask: descriptor.mask.map(|mask| dir.join(mask)),
+        sprite_size,
+        frames,
+        animations,
+    })
+}
+
+#[derive(Deserialize)]
+struct AsepriteSheet {
+    frames: AsepriteFrames,
+    meta: AsepriteMeta,
+}
+
+/// Aseprite writes frames either as an array or as an object keyed by file name.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AsepriteFrames {
+    Array(Vec<AsepriteFrame>),
+    Hash(serde_json::Map<String, serde_json::Value>),
+}
+
+#[derive(Deserialize)]
+struct AsepriteFrame {
+    frame: AsepriteRect,
+    duration: u64,
+}
+
+#[derive(Deserialize)]
+struct AsepriteRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteMeta {
+    image: PathBuf,
+    #[serde(default)]
+    frame_tags: Vec<AsepriteTag>,
+}
+
+#[derive(Deserialize)]
+struct AsepriteTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: String,
+    repeat: Option<String>,
+}
+
+fn aseprite(text: &str, dir: &Path, name: String) -> Result<Sheet, ImportError> {
+    let sheet: AsepriteSheet = serde_json::from_str(text).map_err(ImportError::Json)?;
+    let frames: Vec<AsepriteFrame> = match sheet.frames {
+        AsepriteFrames::Array(frames) => frames,
+        AsepriteFrames::Hash(frames) => frames
+            .into_iter()
+            .map(|(_, frame)| serde_json::from_value(frame))
+            .collect::<Result<_, _>>()
+            .map_err(ImportError::Json)?,
+    };
+    let first = frames
+        .first()
+        .ok_or_else(|| ImportError::Invalid("Aseprite export has no frames".into()))?;
+    let sprite_size = (first.frame.w, first.frame.h);
+
+    for (i, frame) in frames.iter().enumerate() {
+        let rect = &frame.frame;
+        if (rect.w, rect.h) != sprite_size
+            || rect.x % sprite_size.0.max(1) != 0
+            || rect.y % sprite_size.1.max(1) != 0
+        {
+            return Err(ImportError::Invalid(format!(
+                "frame {i} at {},{} size {}x{} is not on the {}x{} grid, export without trimming",
+                rect.x, rect.y, rect.w, rect.h, sprite_size.0, sprite_size.1
+            )));
+        }
+    }
+
+    let cell = |i: usize| {
+        let rect = &frames[i].frame;
+        (rect.x / sprite_size.0, rect.y / sprite_size.1)
+    };
+    let animation = |name: String, range: Vec<usize>, mode: Mode| Animation {
+        name,
+        frames: range.iter().map(|&i| cell(i)).collect(),
+        durations: range.iter().map(|&i| frames[i].duration).collect(),
+        mode,
+    };
+
+    let animations = if sheet.meta.frame_tags.is_empty() {
+        vec![animation(
+            "all".into(),
+            (0..frames.len()).collect(),
+            Mode::Loop,
+        )]
+    } else {
+        let mut animations = Vec::new();
+        for tag in sheet.meta.frame_tags {
+            if tag.from > tag.to || tag.to >= frames.len() {
+                return Err(ImportError::Invalid(format!(
+                    "tag `{}` covers frames {} to {} of {}",
+                    tag.name,
+                    tag.from,
+                    tag.to,
+                    frames.len()
+                )));
+            }
+            // reversed tags become forward animations over the reversed frames
+            let mut range: Vec<usize> = (tag.from..=tag.to).collect();
+            if tag.direction.ends_with("reverse") {
+                range.reverse();
+            }
+            let mode = match (tag.direction.starts_with("pingpong"), tag.repeat.as_deref()) {
+                (true, _) => Mode::PingPong,
+                (false, Some("1")) => Mode::Once,
+                (false, _) => Mode::Loop,
+            };
+            animations.push(animation(tag.name, range, mode));
+        }
+        animations
+    };
+
+    Ok(Sheet {
+        name,
+        source: PathBuf::new(),
+        image: dir.join(sheet.meta.image),
+        mask: None,
+        sprite_size,
+        frames: frames.iter().map(|f| (f.frame.x, f.frame.y)).collect(),
+        animations,
+    })
+}
+
+/// Columns and rows of whole sprites in the BMP at `path`.
+fn grid_size(path: &Path, sprite_size: (u32, u32)) -> Result<(u32, u32), ImportError> {
+    if sprite_size.0 == 0 || sprite_size.1 == 0 {
+        return Err(ImportError::Invalid("sprite size must not be zero".into()));
+    }
+    let data = fs::read(path).map_err(|err| ImportError::Io(path.into(), err))?;
+    let bmp = RawBmp::from_slice(&data).map_err(|err| ImportError::Bmp(path.into(), err))?;
+    let size = bmp.header().image_size;
+    Ok((size.width / sprite_size.0, size.height / sprite_size.1))
+}
+
+impl Sheet {
+    fn validate(&self) -> Result<(), ImportError> {
+        let (columns, rows) = grid_size(&self.image, self.sprite_size)?;
+        if let Some(mask) = &self.mask
+            && grid_size(mask, self.sprite_size)? != (columns, rows)
+        {
+            return Err(ImportError::Invalid(format!(
+                "mask {} does not match the sheet layout",
+                mask.display()
+            )));
+        }
+
+        let mut names: Vec<String> = RESERVED.iter().map(|name| name.to_string()).collect();
+        for animation in &self.animations {
+            if !has_identifier(&animation.name) {
+                return Err(ImportError::Invalid(format!(
+                    "animation name `{}` has no usable identifier, it needs a letter or digit",
+                    animation.name
+                )));
+            }
+            if animation.frames.is_empty() {
+                return Err(ImportError::Invalid(format!(
+                    "animation `{}` has no frames",
+                    animation.name
+                )));
+            }
+            let name = const_name(&animation.name);
+            if names.contains(&name) {
+                return Err(ImportError::Invalid(format!(
+                    "animation name `{}` is used twice or clashes with a generated constant",
+                    animation.name
+                )));
+            }
+            names.push(name);
+
+            if let Some(&(column, row)) = animation
+                .frames
+                .iter()
+                .find(|&&(column, row)| column >= columns || row >= rows)
+            {
+                return Err(ImportError::Invalid(format!(
+                    "animation `{}` uses frame {column},{row} but the sheet is {columns}x{rows} sprites",
+                    animation.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn to_source(&self) -> String {
+        let mut out = String::new();
+        let (width, height) = self.sprite_size;
+        // writing to a String never fails
+        let _ = writeln!(
+            out,
+            "// Generated by kywy::import from {}, do not edit.",
+            self.source.display()
+        );
+        let _ = writeln!(out, "#[allow(dead_code)]");
+        let _ = writeln!(out, "pub mod {} {{", self.name);
+        out.push_str(concat!(
+            "    use ::embassy_time::Duration;\n",
+            "    use ::embedded_graphics::geometry::{Point, Size};\n",
+            "    use ::embedded_graphics::primitives::Rectangle;\n",
+            "    use ::kywy::engine::sprite::{AnimationData, ParseError, PlayMode, SpriteSheet};\n\n",
+        ));
+
+        let _ = writeln!(
+            out,
+            "    pub static IMAGE: &[u8] = include_bytes!({:?});",
+            absolute(&self.image)
+        );
+        match &self.mask {
+            Some(mask) => {
+                let _ = writeln!(
+                    out,
+                    "    pub static MASK: Option<&[u8]> = Some(include_bytes!({:?}));",
+                    absolute(mask)
+                );
+            }
+            None => out.push_str("    pub static MASK: Option<&[u8]> = None;\n"),
+        }
+        let _ = writeln!(
+            out,
+            "    pub const SPRITE_SIZE: Size = Size::new({width}, {height});\n"
+        );
+
+        let _ = writeln!(
+            out,
+            "    /// Frame rectangles in the sheet, in export order\n    pub const FRAMES: [Rectangle; {}] = [",
+            self.frames.len()
+        );
+        for (x, y) in &self.frames {
+            let _ = writeln!(
+                out,
+                "        Rectangle::new(Point::new({x}, {y}), SPRITE_SIZE),"
+            );
+        }
+        out.push_str("    ];\n\n");
+
+        for animation in &self.animations {
+            let _ = writeln!(
+                out,
+                "    pub const {}: AnimationData<'static> = AnimationData {{",
+                const_name(&animation.name)
+            );
+            let frames: Vec<String> = animation
+                .frames
+                .iter()
+                .map(|(x, y)| format!("({x}, {y})"))
+                .collect();
+            let _ = writeln!(out, "        frames: &[{}],", frames.join(", "));
+            let durations: Vec<String> = animation
+                .durations
+                .iter()
+                .map(|ms| format!("Duration::from_millis({ms})"))
+                .collect();
+            let _ = writeln!(out, "        durations: &[{}],", durations.join(", "));
+            let mode = match animation.mode {
+                Mode::Once => "Once",
+                Mode::Loop => "Loop",
+                Mode::PingPong => "PingPong",
+                Mode::Reverse => "Reverse",
+            };
+            let _ = writeln!(out, "        mode: PlayMode::{mode},\n    }};\n");
+        }
+
+        let _ = writeln!(
+            out,
+            "    /// Every animation with its name from the descriptor\n    pub const ANIMATIONS: [(&str, AnimationData<'static>); {}] = [",
+            self.animations.len()
+        );
+        for animation in &self.animations {
+            let _ = writeln!(
+                out,
+                "        ({:?}, {}),",
+                animation.name,
+                const_name(&animation.name)
+            );
+        }
+        out.push_str("    ];\n\n");
+
+        out.push_str(concat!(
+            "    pub fn sheet() -> Result<SpriteSheet<'static>, ParseError> {\n",
+            "        let sheet = SpriteSheet::new(IMAGE, SPRITE_SIZE)?;\n",
+            "        match MASK {\n",
+            "            Some(mask) => sheet.with_mask(mask),\n",
+            "            None => Ok(sheet),\n",
+            "        }\n",
+            "    }\n",
+            "}\n",
+        ));
+        out
+    }
+}
+
+/// Module name from the descriptor file name, `Electric Monster.json` becomes `electric_monster`.
+fn module_name(descriptor: &Path) -> Result<String, ImportError> {
+    let stem = descriptor
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    let name = identifier(stem).to_lowercase();
+    if name.is_empty() {
+        return Err(ImportError::Invalid(format!(
+            "{} has no usable file name",
+            descriptor.display()
+        )));
+    }
+    Ok(name)
+}
+
+/// Constant name for an animation, `walk left` becomes `WALK_LEFT`.
+fn const_name(name: &str) -> String {
+    match identifier(name).to_uppercase() {
+        name if name.is_empty() => "_".into(),
+        name => name,
+    }
+}
+
+/// Returns true if `name` turns into an identifier of more than underscores.
+fn has_identifier(name: &str) -> bool {
+    name.chars().any(|c| c.is_ascii_alphanumeric())
+}
+
+fn identifier(name: &str) -> String {
+    let mut ident: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+/// `include_bytes!` resolves relative paths against the generated file in `OUT_DIR`.
+fn absolute(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.into())
+}
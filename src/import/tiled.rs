@@ -0,0 +1,869 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tiled map import, see [`build_map`](super::build_map).
+
+use super::{ImportError, absolute, const_name, grid_size, has_identifier, module_name};
+use roxmltree::Node;
+use serde::Deserialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Tiled keeps flips and rotations in the top bits of a tile id
+const FLIP_BITS: u32 = 0xf000_0000;
+
+/// Names the generated module already uses
+const RESERVED: [&str; 7] = [
+    "IMAGE",
+    "TILE_SIZE",
+    "COLUMNS",
+    "ROWS",
+    "PROPERTIES",
+    "LAYERS",
+    "OBJECT_LAYERS",
+];
+
+/// Map in the form the generated module uses.
+pub(super) struct Map {
+    pub(super) name: String,
+    source: PathBuf,
+    pub(super) tileset: Tileset,
+    tile_size: (u32, u32),
+    columns: u32,
+    rows: u32,
+    layers: Vec<TileLayer>,
+    object_layers: Vec<ObjectLayer>,
+    properties: Vec<(String, Value)>,
+}
+
+pub(super) struct Tileset {
+    /// External tileset file, if the map does not embed it
+    pub(super) source: Option<PathBuf>,
+    pub(super) image: PathBuf,
+    first_gid: u32,
+    tile_size: (u32, u32),
+    /// Tileset tile id and the flags from its properties
+    flags: Vec<(u32, u8)>,
+}
+
+struct TileLayer {
+    name: String,
+    /// Raw Tiled ids, row by row
+    gids: Vec<u32>,
+}
+
+struct ObjectLayer {
+    name: String,
+    objects: Vec<Object>,
+}
+
+struct Object {
+    name: String,
+    kind: String,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    gid: u32,
+    properties: Vec<(String, Value)>,
+}
+
+enum Value {
+    Bool(bool),
+    Int(i32),
+    Float(f32),
+    Str(String),
+}
+
+pub(super) fn load(path: &Path) -> Result<Map, ImportError> {
+    let text = fs::read_to_string(path).map_err(|err| ImportError::Io(path.into(), err))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let name = module_name(path)?;
+
+    let map = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("tmx") => tmx(&text, dir, name)?,
+        _ => tmj(&text, dir, name)?,
+    };
+    map.validate()?;
+    Ok(Map {
+        source: path.into(),
+        ..map
+    })
+}
+
+fn invalid(msg: impl Into<String>) -> ImportError {
+    ImportError::Invalid(msg.into())
+}
+
+/// Converts a property from its editor type and text form.
+fn value(name: &str, kind: &str, text: &str) -> Result<Value, ImportError> {
+    let bad = || invalid(format!("property `{name}` is not a valid {kind}: {text}"));
+    Ok(match kind {
+        "bool" => Value::Bool(text.parse().map_err(|_| bad())?),
+        // object references are stored as the object id
+        "int" | "object" => Value::Int(text.parse().map_err(|_| bad())?),
+        "float" => Value::Float(text.parse().map_err(|_| bad())?),
+        "string" | "color" | "file" => Value::Str(text.into()),
+        _ => {
+            return Err(invalid(format!(
+                "property `{name}` has type {kind}, only bool, int, float, string, color, file and object are supported"
+            )));
+        }
+    })
+}
+
+/// Flags of a tileset tile from its `solid`, `platform` and `flags` properties.
+fn tile_flags(properties: &[(String, Value)]) -> u8 {
+    properties
+        .iter()
+        .fold(0, |flags, (name, value)| match (name.as_str(), value) {
+            ("solid", Value::Bool(true)) => flags | 1,
+            ("platform", Value::Bool(true)) => flags | 2,
+            ("flags", Value::Int(bits)) => flags | *bits as u8,
+            _ => flags,
+        })
+}
+
+#[derive(Deserialize)]
+struct JsonMap {
+    #[serde(default)]
+    orientation: Option<String>,
+    #[serde(default)]
+    infinite: bool,
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    layers: Vec<JsonLayer>,
+    tilesets: Vec<JsonTileset>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonLayer {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    data: Option<serde_json::Value>,
+    encoding: Option<String>,
+    compression: Option<String>,
+    #[serde(default)]
+    objects: Vec<JsonObject>,
+    /// Children of group layers
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+}
+
+#[derive(Deserialize)]
+struct JsonObject {
+    #[serde(default)]
+    name: String,
+    /// `type` before Tiled 1.9, `class` after
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    class: String,
+    x: f64,
+    y: f64,
+    #[serde(default)]
+    width: f64,
+    #[serde(default)]
+    height: f64,
+    #[serde(default)]
+    gid: u32,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonProperty {
+    name: String,
+    #[serde(default = "string_type", rename = "type")]
+    kind: String,
+    value: serde_json::Value,
+}
+
+fn string_type() -> String {
+    "string".into()
+}
+
+#[derive(Deserialize)]
+struct JsonTileset {
+    #[serde(default)]
+    firstgid: u32,
+    source: Option<PathBuf>,
+    image: Option<PathBuf>,
+    tilewidth: Option<u32>,
+    tileheight: Option<u32>,
+    #[serde(default)]
+    margin: u32,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default)]
+    tiles: Vec<JsonTile>,
+}
+
+#[derive(Deserialize)]
+struct JsonTile {
+    id: u32,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+fn json_properties(properties: Vec<JsonProperty>) -> Result<Vec<(String, Value)>, ImportError> {
+    properties
+        .into_iter()
+        .map(|property| {
+            let text = match property.value {
+                serde_json::Value::String(text) => text,
+                value @ (serde_json::Value::Bool(_) | serde_json::Value::Number(_)) => {
+                    value.to_string()
+                }
+                _ => {
+                    return Err(invalid(format!(
+                        "property `{}` has type {}, only bool, int, float, string, color, file and object are supported",
+                        property.name, property.kind
+                    )));
+                }
+            };
+            let value = value(&property.name, &property.kind, &text)?;
+            Ok((property.name, value))
+        })
+        .collect()
+}
+
+fn tmj(text: &str, dir: &Path, name: String) -> Result<Map, ImportError> {
+    let map: JsonMap = serde_json::from_str(text).map_err(ImportError::Json)?;
+    check_layout(map.orientation.as_deref(), map.infinite)?;
+    let tileset = match <[_; 1]>::try_from(map.tilesets) {
+        Ok([tileset]) => json_tileset(tileset, dir)?,
+        Err(tilesets) => return Err(tileset_count(tilesets.len())),
+    };
+
+    let mut layers = Vec::new();
+    let mut object_layers = Vec::new();
+    json_layers(map.layers, &mut layers, &mut object_layers)?;
+
+    Ok(Map {
+        name,
+        source: PathBuf::new(),
+        tileset,
+        tile_size: (map.tilewidth, map.tileheight),
+        columns: map.width,
+        rows: map.height,
+        layers,
+        object_layers,
+        properties: json_properties(map.properties)?,
+    })
+}
+
+/// Flattens group layers into tile and object layers, in editor order.
+fn json_layers(
+    json: Vec<JsonLayer>,
+    layers: &mut Vec<TileLayer>,
+    object_layers: &mut Vec<ObjectLayer>,
+) -> Result<(), ImportError> {
+    for layer in json {
+        match layer.kind.as_str() {
+            "tilelayer" => {
+                if layer.compression.as_deref().is_some_and(|c| !c.is_empty()) {
+                    return Err(compressed(&layer.name));
+                }
+                let gids = match (layer.data, layer.encoding.as_deref()) {
+                    (Some(serde_json::Value::String(data)), Some("base64")) => {
+                        base64_gids(&layer.name, &data)?
+                    }
+                    (Some(data), _) => serde_json::from_value(data).map_err(ImportError::Json)?,
+                    (None, _) => {
+                        return Err(invalid(format!("layer `{}` has no tile data", layer.name)));
+                    }
+                };
+                layers.push(TileLayer {
+                    name: layer.name,
+                    gids,
+                });
+            }
+            "objectgroup" => {
+                let objects = layer
+                    .objects
+                    .into_iter()
+                    .map(|object| {
+                        Ok(Object {
+                            name: object.name,
+                            kind: if object.class.is_empty() {
+                                object.kind
+                            } else {
+                                object.class
+                            },
+                            x: object.x,
+                            y: object.y,
+                            width: object.width,
+                            height: object.height,
+                            gid: object.gid,
+                            properties: json_properties(object.properties)?,
+                        })
+                    })
+                    .collect::<Result<_, ImportError>>()?;
+                object_layers.push(ObjectLayer {
+                    name: layer.name,
+                    objects,
+                });
+            }
+            "group" => json_layers(layer.layers, layers, object_layers)?,
+            // image layers have nothing a tilemap can use
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn json_tileset(tileset: JsonTileset, dir: &Path) -> Result<Tileset, ImportError> {
+    let first_gid = tileset.firstgid.max(1);
+    if let Some(source) = tileset.source {
+        return external_tileset(&dir.join(source), first_gid);
+    }
+    if tileset.margin != 0 || tileset.spacing != 0 {
+        return Err(spaced());
+    }
+    let (Some(image), Some(width), Some(height)) =
+        (tileset.image, tileset.tilewidth, tileset.tileheight)
+    else {
+        return Err(invalid(
+            "the tileset has to be a single image, image collections are not supported",
+        ));
+    };
+    let flags = tileset
+        .tiles
+        .into_iter()
+        .map(|tile| Ok((tile.id, tile_flags(&json_properties(tile.properties)?))))
+        .collect::<Result<_, ImportError>>()?;
+    Ok(Tileset {
+        source: None,
+        image: dir.join(image),
+        first_gid,
+        tile_size: (width, height),
+        flags,
+    })
+}
+
+/// Loads a `.tsx` or `.tsj` tileset file.
+fn external_tileset(path: &Path, first_gid: u32) -> Result<Tileset, ImportError> {
+    let text = fs::read_to_string(path).map_err(|err| ImportError::Io(path.into(), err))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let tileset = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("tsx") => {
+            let doc = roxmltree::Document::parse(&text).map_err(ImportError::Xml)?;
+            xml_tileset(doc.root_element(), dir, first_gid)?
+        }
+        _ => {
+            let tileset: JsonTileset = serde_json::from_str(&text).map_err(ImportError::Json)?;
+            json_tileset(
+                JsonTileset {
+                    firstgid: first_gid,
+                    ..tileset
+                },
+                dir,
+            )?
+        }
+    };
+    Ok(Tileset {
+        source: Some(path.into()),
+        ..tileset
+    })
+}
+
+fn attribute<T: FromStr>(node: Node, name: &str) -> Result<Option<T>, ImportError> {
+    node.attribute(name)
+        .map(|text| {
+            text.parse().map_err(|_| {
+                invalid(format!(
+                    "<{}> has an invalid {name}: {text}",
+                    node.tag_name().name()
+                ))
+            })
+        })
+        .transpose()
+}
+
+fn required<T: FromStr>(node: Node, name: &str) -> Result<T, ImportError> {
+    attribute(node, name)?
+        .ok_or_else(|| invalid(format!("<{}> is missing {name}", node.tag_name().name())))
+}
+
+fn children<'a, 'i>(node: Node<'a, 'i>, tag: &'a str) -> impl Iterator<Item = Node<'a, 'i>> {
+    node.children().filter(move |child| child.has_tag_name(tag))
+}
+
+fn xml_properties(node: Node) -> Result<Vec<(String, Value)>, ImportError> {
+    children(node, "properties")
+        .flat_map(|properties| children(properties, "property"))
+        .map(|property| {
+            let name: String = required(property, "name")?;
+            let kind = property.attribute("type").unwrap_or("string");
+            // multi line strings are stored as text instead of an attribute
+            let text = property
+                .attribute("value")
+                .or_else(|| property.text())
+                .unwrap_or_default();
+            let value = value(&name, kind, text)?;
+            Ok((name, value))
+        })
+        .collect()
+}
+
+fn tmx(text: &str, dir: &Path, name: String) -> Result<Map, ImportError> {
+    let doc = roxmltree::Document::parse(text).map_err(ImportError::Xml)?;
+    let map = doc.root_element();
+    if !map.has_tag_name("map") {
+        return Err(invalid("not a Tiled map, the root element is not <map>"));
+    }
+    check_layout(
+        map.attribute("orientation"),
+        attribute::<u8>(map, "infinite")? == Some(1),
+    )?;
+
+    let tilesets: Vec<_> = children(map, "tileset").collect();
+    let tileset = match tilesets[..] {
+        [tileset] => {
+            let first_gid = attribute(tileset, "firstgid")?.unwrap_or(1);
+            match tileset.attribute("source") {
+                Some(source) => external_tileset(&dir.join(source), first_gid)?,
+                None => xml_tileset(tileset, dir, first_gid)?,
+            }
+        }
+        _ => return Err(tileset_count(tilesets.len())),
+    };
+
+    let mut layers = Vec::new();
+    let mut object_layers = Vec::new();
+    xml_layers(map, &mut layers, &mut object_layers)?;
+
+    Ok(Map {
+        name,
+        source: PathBuf::new(),
+        tileset,
+        tile_size: (required(map, "tilewidth")?, required(map, "tileheight")?),
+        columns: required(map, "width")?,
+        rows: required(map, "height")?,
+        layers,
+        object_layers,
+        properties: xml_properties(map)?,
+    })
+}
+
+fn xml_tileset(node: Node, dir: &Path, first_gid: u32) -> Result<Tileset, ImportError> {
+    if attribute::<u32>(node, "margin")?.unwrap_or(0) != 0
+        || attribute::<u32>(node, "spacing")?.unwrap_or(0) != 0
+    {
+        return Err(spaced());
+    }
+    let Some(image) = children(node, "image").next() else {
+        return Err(invalid(
+            "the tileset has to be a single image, image collections are not supported",
+        ));
+    };
+    let flags = children(node, "tile")
+        .map(|tile| Ok((required(tile, "id")?, tile_flags(&xml_properties(tile)?))))
+        .collect::<Result<_, ImportError>>()?;
+    Ok(Tileset {
+        source: None,
+        image: dir.join(required::<PathBuf>(image, "source")?),
+        first_gid,
+        tile_size: (required(node, "tilewidth")?, required(node, "tileheight")?),
+        flags,
+    })
+}
+
+/// Flattens group layers into tile and object layers, in editor order.
+fn xml_layers(
+    node: Node,
+    layers: &mut Vec<TileLayer>,
+    object_layers: &mut Vec<ObjectLayer>,
+) -> Result<(), ImportError> {
+    for child in node.children() {
+        let name = child.attribute("name").unwrap_or_default().to_string();
+        match child.tag_name().name() {
+            "layer" => {
+                let data = children(child, "data")
+                    .next()
+                    .ok_or_else(|| invalid(format!("layer `{name}` has no tile data")))?;
+                if data.attribute("compression").is_some() {
+                    return Err(compressed(&name));
+                }
+                let text = data.text().unwrap_or_default();
+                let gids = match data.attribute("encoding") {
+                    Some("csv") => text
+                        .split(',')
+                        .map(|gid| gid.trim().parse())
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| invalid(format!("layer `{name}` has invalid CSV data")))?,
+                    Some("base64") => base64_gids(&name, text)?,
+                    _ => children(data, "tile")
+                        .map(|tile| Ok(attribute(tile, "gid")?.unwrap_or(0)))
+                        .collect::<Result<_, ImportError>>()?,
+                };
+                layers.push(TileLayer { name, gids });
+            }
+            "objectgroup" => {
+                let objects = children(child, "object")
+                    .map(|object| {
+                        Ok(Object {
+                            name: object.attribute("name").unwrap_or_default().into(),
+                            kind: object
+                                .attribute("class")
+                                .or_else(|| object.attribute("type"))
+                                .unwrap_or_default()
+                                .into(),
+                            x: required(object, "x")?,
+                            y: required(object, "y")?,
+                            width: attribute(object, "width")?.unwrap_or(0.0),
+                            height: attribute(object, "height")?.unwrap_or(0.0),
+                            gid: attribute(object, "gid")?.unwrap_or(0),
+                            properties: xml_properties(object)?,
+                        })
+                    })
+                    .collect::<Result<_, ImportError>>()?;
+                object_layers.push(ObjectLayer { name, objects });
+            }
+            "group" => xml_layers(child, layers, object_layers)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn check_layout(orientation: Option<&str>, infinite: bool) -> Result<(), ImportError> {
+    if orientation.is_some_and(|orientation| orientation != "orthogonal") {
+        return Err(invalid("only orthogonal maps are supported"));
+    }
+    if infinite {
+        return Err(invalid(
+            "infinite maps are not supported, turn off Infinite in the map properties",
+        ));
+    }
+    Ok(())
+}
+
+fn tileset_count(count: usize) -> ImportError {
+    invalid(format!(
+        "the map uses {count} tilesets, a tilemap draws from exactly one"
+    ))
+}
+
+fn spaced() -> ImportError {
+    invalid(
+        "tilesets with margin or spacing are not supported, the sprite sheet has to be a plain grid",
+    )
+}
+
+fn compressed(layer: &str) -> ImportError {
+    invalid(format!(
+        "layer `{layer}` is compressed, save the map with CSV or uncompressed Base64 layer format"
+    ))
+}
+
+/// Decodes uncompressed Base64 layer data, little endian u32 per tile.
+fn base64_gids(layer: &str, text: &str) -> Result<Vec<u32>, ImportError> {
+    let bad = || invalid(format!("layer `{layer}` has invalid Base64 data"));
+    let mut bytes = Vec::new();
+    let (mut bits, mut count) = (0u32, 0);
+    for c in text
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace() && *c != b'=')
+    {
+        let sextet = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(bad()),
+        };
+        bits = bits << 6 | sextet as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+        }
+    }
+    if bytes.len() % 4 != 0 {
+        return Err(bad());
+    }
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]]))
+        .collect())
+}
+
+impl Map {
+    /// Tilemap tile for a Tiled id, `Err` holds what is wrong with it.
+    fn tile(&self, gid: u32, tile_count: u32) -> Result<u16, String> {
+        if gid == 0 {
+            return Ok(0);
+        }
+        if gid & FLIP_BITS != 0 {
+            return Err("is flipped or rotated, which the tilemap does not support".into());
+        }
+        let Some(id) = gid.checked_sub(self.tileset.first_gid) else {
+            return Err(format!("uses tile id {gid}, which is not in the tileset"));
+        };
+        if id >= tile_count {
+            return Err(format!(
+                "uses tile id {id} but {} only has {tile_count} tiles",
+                self.tileset.image.display()
+            ));
+        }
+        Ok(id as u16 + 1)
+    }
+
+    fn tile_count(&self) -> Result<u32, ImportError> {
+        let (columns, rows) = grid_size(&self.tileset.image, self.tile_size)?;
+        Ok(columns * rows)
+    }
+
+    fn validate(&self) -> Result<(), ImportError> {
+        if self.tileset.tile_size != self.tile_size {
+            return Err(invalid(format!(
+                "the tileset has {}x{} tiles but the map uses {}x{}",
+                self.tileset.tile_size.0,
+                self.tileset.tile_size.1,
+                self.tile_size.0,
+                self.tile_size.1
+            )));
+        }
+        let tile_count = self.tile_count()?;
+        if tile_count > u16::MAX as u32 {
+            return Err(invalid(format!(
+                "{} has {tile_count} tiles, a tilemap can use at most {}",
+                self.tileset.image.display(),
+                u16::MAX
+            )));
+        }
+
+        let mut names: Vec<String> = RESERVED.iter().map(|name| name.to_string()).collect();
+        let layer_names = self.layers.iter().map(|layer| &layer.name);
+        let object_names = self.object_layers.iter().map(|layer| &layer.name);
+        for name in layer_names.chain(object_names) {
+            if !has_identifier(name) {
+                return Err(invalid(format!(
+                    "layer name `{name}` has no usable identifier, it needs a letter or digit"
+                )));
+            }
+            let constant = const_name(name);
+            if names.contains(&constant) {
+                return Err(invalid(format!(
+                    "layer name `{name}` is used twice or clashes with a generated constant"
+                )));
+            }
+            names.push(constant);
+        }
+
+        let cells = self.columns as usize * self.rows as usize;
+        for layer in &self.layers {
+            if layer.gids.len() != cells {
+                return Err(invalid(format!(
+                    "layer `{}` has {} tiles but the map is {}x{}",
+                    layer.name,
+                    layer.gids.len(),
+                    self.columns,
+                    self.rows
+                )));
+            }
+            for (i, &gid) in layer.gids.iter().enumerate() {
+                self.tile(gid, tile_count).map_err(|err| {
+                    let (column, row) = (i as u32 % self.columns, i as u32 / self.columns);
+                    invalid(format!(
+                        "layer `{}` at column {column}, row {row} {err}",
+                        layer.name
+                    ))
+                })?;
+            }
+        }
+        for layer in &self.object_layers {
+            for object in &layer.objects {
+                self.tile(object.gid, tile_count).map_err(|err| {
+                    invalid(format!(
+                        "object `{}` in layer `{}` {err}",
+                        object.name, layer.name
+                    ))
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn to_source(&self) -> String {
+        // validate has already checked every tile
+        let tile_count = self.tile_count().unwrap_or(u32::MAX);
+        let tile = |gid| self.tile(gid, tile_count).unwrap_or(0);
+        let mut out = String::new();
+        let (width, height) = self.tile_size;
+        // writing to a String never fails
+        let _ = writeln!(
+            out,
+            "// Generated by kywy::import from {}, do not edit.",
+            self.source.display()
+        );
+        let _ = writeln!(out, "#[allow(dead_code)]");
+        let _ = writeln!(out, "pub mod {} {{", self.name);
+        out.push_str(concat!(
+            "    use ::embedded_graphics::geometry::{Point, Size};\n",
+            "    use ::kywy::engine::sprite::{ParseError, SpriteSheet};\n",
+            "    use ::kywy::engine::tilemap::{MapObject, Property, TileFlags, TilemapData};\n\n",
+        ));
+
+        let _ = writeln!(
+            out,
+            "    pub static IMAGE: &[u8] = include_bytes!({:?});",
+            absolute(&self.tileset.image)
+        );
+        let _ = writeln!(
+            out,
+            "    pub const TILE_SIZE: Size = Size::new({width}, {height});"
+        );
+        let _ = writeln!(out, "    pub const COLUMNS: u32 = {};", self.columns);
+        let _ = writeln!(out, "    pub const ROWS: u32 = {};\n", self.rows);
+
+        let _ = writeln!(
+            out,
+            "    /// Custom properties of the map\n    pub const PROPERTIES: [(&str, Property<'static>); {}] = [",
+            self.properties.len()
+        );
+        for (name, value) in &self.properties {
+            let _ = writeln!(out, "        ({name:?}, {}),", value.to_source());
+        }
+        out.push_str("    ];\n\n");
+
+        let flags_of = |gid: u32| {
+            let id = tile(gid).checked_sub(1)? as u32;
+            self.tileset
+                .flags
+                .iter()
+                .find(|&&(tile, _)| tile == id)
+                .map(|&(_, flags)| flags)
+        };
+        for layer in &self.layers {
+            let _ = writeln!(
+                out,
+                "    pub const {}: TilemapData<'static> = TilemapData {{",
+                const_name(&layer.name)
+            );
+            let _ = writeln!(out, "        columns: COLUMNS,\n        tiles: &[");
+            for row in layer.gids.chunks(self.columns.max(1) as usize) {
+                let tiles: Vec<String> = row.iter().map(|&gid| tile(gid).to_string()).collect();
+                let _ = writeln!(out, "            {},", tiles.join(", "));
+            }
+            out.push_str("        ],\n");
+            // layers without flagged tiles leave the flag layer empty
+            if layer.gids.iter().any(|&gid| flags_of(gid).is_some()) {
+                out.push_str("        flags: &[\n");
+                for row in layer.gids.chunks(self.columns.max(1) as usize) {
+                    let flags: Vec<String> = row
+                        .iter()
+                        .map(|&gid| format!("TileFlags({})", flags_of(gid).unwrap_or(0)))
+                        .collect();
+                    let _ = writeln!(out, "            {},", flags.join(", "));
+                }
+                out.push_str("        ],\n");
+            } else {
+                out.push_str("        flags: &[],\n");
+            }
+            out.push_str("    };\n\n");
+        }
+
+        for layer in &self.object_layers {
+            let _ = writeln!(
+                out,
+                "    pub const {}: [MapObject<'static>; {}] = [",
+                const_name(&layer.name),
+                layer.objects.len()
+            );
+            for object in &layer.objects {
+                // Tiled places tile objects by their bottom left corner
+                let y = match object.gid {
+                    0 => object.y,
+                    _ => object.y - object.height,
+                };
+                let properties: Vec<String> = object
+                    .properties
+                    .iter()
+                    .map(|(name, value)| format!("({name:?}, {})", value.to_source()))
+                    .collect();
+                let _ = writeln!(
+                    out,
+                    concat!(
+                        "        MapObject {{\n",
+                        "            name: {:?},\n",
+                        "            kind: {:?},\n",
+                        "            position: Point::new({}, {}),\n",
+                        "            size: Size::new({}, {}),\n",
+                        "            tile: {},\n",
+                        "            properties: &[{}],\n",
+                        "        }},",
+                    ),
+                    object.name,
+                    object.kind,
+                    object.x.round() as i32,
+                    y.round() as i32,
+                    object.width.round().max(0.0) as u32,
+                    object.height.round().max(0.0) as u32,
+                    tile(object.gid),
+                    properties.join(", ")
+                );
+            }
+            out.push_str("    ];\n\n");
+        }
+
+        let _ = writeln!(
+            out,
+            "    /// Every tile layer with its name from the map\n    pub const LAYERS: [(&str, TilemapData<'static>); {}] = [",
+            self.layers.len()
+        );
+        for layer in &self.layers {
+            let _ = writeln!(
+                out,
+                "        ({:?}, {}),",
+                layer.name,
+                const_name(&layer.name)
+            );
+        }
+        out.push_str("    ];\n\n");
+
+        let _ = writeln!(
+            out,
+            "    /// Every object layer with its name from the map\n    pub const OBJECT_LAYERS: [(&str, &[MapObject<'static>]); {}] = [",
+            self.object_layers.len()
+        );
+        for layer in &self.object_layers {
+            let _ = writeln!(
+                out,
+                "        ({:?}, &{}),",
+                layer.name,
+                const_name(&layer.name)
+            );
+        }
+        out.push_str("    ];\n\n");
+
+        out.push_str(concat!(
+            "    pub fn tileset() -> Result<SpriteSheet<'static>, ParseError> {\n",
+            "        SpriteSheet::new(IMAGE, TILE_SIZE)\n",
+            "    }\n",
+            "}\n",
+        ));
+        out
+    }
+}
+
+impl Value {
+    fn to_source(&self) -> String {
+        match self {
+            Value::Bool(value) => format!("Property::Bool({value})"),
+            Value::Int(value) => format!("Property::Int({value})"),
+            Value::Float(value) => format!("Property::Float({value:?})"),
+            Value::Str(value) => format!("Property::Str({value:?})"),
+        }
+    }
+}
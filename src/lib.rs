@@ -1,17 +1,35 @@
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 // SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 //
 #![doc = include_str!("../README.md")]
 
+// Hardware drivers, only available when building for the device
+#[cfg(target_os = "none")]
 pub mod battery;
-pub mod button_async;
+#[cfg(target_os = "none")]
 pub mod button_poll;
+#[cfg(target_os = "none")]
 pub mod display;
+#[cfg(target_os = "none")]
+pub mod usb;
+
+// Hardware independent, also builds on a host for tests
+pub mod button_async;
+pub mod dither;
+pub mod double_buffer;
 pub mod engine;
+pub mod framebuffer;
 pub mod sdcard;
-pub mod usb;
+
+// Host side simulator, requires the `std` feature
+#[cfg(feature = "std")]
+pub mod simulator;
+
+// Build script helpers, require the `import` feature
+#[cfg(feature = "import")]
+pub mod import;
 
 #[macro_use]
 pub mod macros;
@@ -65,7 +65,7 @@ macro_rules! kywy_display_from {
         );
 
         //initialize display
-        $var.initialize().await;
+        $var.initialize().await.expect("Failed to initialize display");
     };
 }
 
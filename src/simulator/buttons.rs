@@ -0,0 +1,77 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Scripted button input for the simulator.
+//! Events are scheduled on game ticks and handed out with the same
+//! `try_receive` call games use on the async button channel.
+
+use crate::button_async::{ButtonEvent, ButtonId, ButtonState};
+use embassy_sync::channel::TryReceiveError;
+
+const BUTTON_COUNT: usize = 7;
+
+pub struct ScriptedButtons {
+    script: Vec<(u32, ButtonEvent)>,
+    next: usize,
+    tick: u32,
+    held: [bool; BUTTON_COUNT],
+}
+
+impl ScriptedButtons {
+    /// Creates a script from `(tick, event)` pairs, in any order.
+    pub fn new(script: impl IntoIterator<Item = (u32, ButtonEvent)>) -> Self {
+        let mut script: Vec<_> = script.into_iter().collect();
+        script.sort_by_key(|(tick, _)| *tick);
+
+        Self {
+            script,
+            next: 0,
+            tick: 0,
+            held: [false; BUTTON_COUNT],
+        }
+    }
+
+    /// Adds a press at `tick` and a release `duration` ticks later.
+    pub fn tap(mut self, tick: u32, id: ButtonId, duration: u32) -> Self {
+        self.push(tick, id, ButtonState::Pressed);
+        self.push(tick + duration, id, ButtonState::Released);
+        self
+    }
+
+    fn push(&mut self, tick: u32, id: ButtonId, state: ButtonState) {
+        let index = self.script.partition_point(|(t, _)| *t <= tick);
+        self.script.insert(index, (tick, ButtonEvent { id, state }));
+    }
+
+    /// Returns the next event due at or before the current tick.
+    pub fn try_receive(&mut self) -> Result<ButtonEvent, TryReceiveError> {
+        match self.script.get(self.next) {
+            Some(&(tick, event)) if tick <= self.tick => {
+                self.next += 1;
+                self.held[event.id as usize] = event.state == ButtonState::Pressed;
+                Ok(event)
+            }
+            _ => Err(TryReceiveError::Empty),
+        }
+    }
+
+    /// Moves on to the next game tick.
+    pub fn advance(&mut self) {
+        self.tick += 1;
+    }
+
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    /// Held state of a button from the events received so far, like `ButtonPoller`.
+    pub fn is_pressed(&self, id: ButtonId) -> bool {
+        self.held[id as usize]
+    }
+
+    /// Returns true once every scripted event has been received.
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.script.len()
+    }
+}
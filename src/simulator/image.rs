@@ -0,0 +1,140 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Image file encoding for simulator frames.
+//! PBM stores 1 as black, so On (white) pixels are written as 0.
+
+use crate::framebuffer::{Framebuffer, HEIGHT, WIDTH};
+use core::fmt;
+use embedded_graphics::pixelcolor::BinaryColor;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PbmError {
+    /// Not a P1 (ASCII) or P4 (binary) bitmap
+    UnsupportedFormat,
+    /// The image is not 144x168
+    WrongSize { width: usize, height: usize },
+    /// Header or pixel data ended early or is malformed
+    Malformed,
+}
+
+impl fmt::Display for PbmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PbmError::UnsupportedFormat => write!(f, "only P1 and P4 PBM images are supported"),
+            PbmError::WrongSize { width, height } => write!(
+                f,
+                "image is {}x{}, expected {}x{}",
+                width, height, WIDTH, HEIGHT
+            ),
+            PbmError::Malformed => write!(f, "malformed PBM data"),
+        }
+    }
+}
+
+impl std::error::Error for PbmError {}
+
+/// Encodes the buffer as a binary (P4) PBM image.
+pub fn encode_pbm(framebuffer: &Framebuffer) -> Vec<u8> {
+    let mut out = Vec::with_capacity(16 + framebuffer.as_bytes().len());
+    out.extend_from_slice(format!("P4\n{} {}\n", WIDTH, HEIGHT).as_bytes());
+    // PBM rows are MSB first and 1 means black
+    out.extend(framebuffer.as_bytes().iter().map(|b| !b.reverse_bits()));
+    out
+}
+
+/// Encodes the buffer as a 1 bit grayscale PNG.
+pub fn write_png<W: Write>(framebuffer: &Framebuffer, writer: W) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(writer, WIDTH as u32, HEIGHT as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::One);
+
+    // PNG rows are MSB first and 1 means white, matching BinaryColor::On
+    let data: Vec<u8> = framebuffer
+        .as_bytes()
+        .iter()
+        .map(|b| b.reverse_bits())
+        .collect();
+
+    let mut writer = encoder.write_header().map_err(io::Error::other)?;
+    writer.write_image_data(&data).map_err(io::Error::other)?;
+    writer.finish().map_err(io::Error::other)
+}
+
+/// Decodes a P1 or P4 PBM image with the display size into a framebuffer.
+pub fn decode_pbm(data: &[u8]) -> Result<Framebuffer, PbmError> {
+    let mut pos = 0;
+    let magic = next_token(data, &mut pos).ok_or(PbmError::Malformed)?;
+    let binary = match magic {
+        b"P4" => true,
+        b"P1" => false,
+        _ => return Err(PbmError::UnsupportedFormat),
+    };
+    let width = parse_number(next_token(data, &mut pos))?;
+    let height = parse_number(next_token(data, &mut pos))?;
+    if width != WIDTH || height != HEIGHT {
+        return Err(PbmError::WrongSize { width, height });
+    }
+
+    let mut framebuffer = Framebuffer::new();
+    if binary {
+        // exactly one whitespace byte separates the header from the data
+        let pixels = data.get(pos + 1..).ok_or(PbmError::Malformed)?;
+        let row_bytes = WIDTH.div_ceil(8);
+        if pixels.len() < row_bytes * HEIGHT {
+            return Err(PbmError::Malformed);
+        }
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let black = pixels[y * row_bytes + x / 8] & (0x80 >> (x % 8)) != 0;
+                framebuffer.set_raw_pixel(x, y, BinaryColor::from(!black));
+            }
+        }
+    } else {
+        let mut digits = data[pos..]
+            .iter()
+            .filter(|b| !b.is_ascii_whitespace())
+            .copied();
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let black = match digits.next() {
+                    Some(b'1') => true,
+                    Some(b'0') => false,
+                    _ => return Err(PbmError::Malformed),
+                };
+                framebuffer.set_raw_pixel(x, y, BinaryColor::from(!black));
+            }
+        }
+    }
+    Ok(framebuffer)
+}
+
+/// Returns the next whitespace separated header token, skipping `#` comments.
+fn next_token<'a>(data: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    loop {
+        match data.get(*pos)? {
+            b'#' => {
+                while data.get(*pos).is_some_and(|&b| b != b'\n') {
+                    *pos += 1;
+                }
+            }
+            b if b.is_ascii_whitespace() => *pos += 1,
+            _ => break,
+        }
+    }
+
+    let start = *pos;
+    while data.get(*pos).is_some_and(|b| !b.is_ascii_whitespace()) {
+        *pos += 1;
+    }
+    Some(&data[start..*pos])
+}
+
+fn parse_number(token: Option<&[u8]>) -> Result<usize, PbmError> {
+    token
+        .and_then(|t| core::str::from_utf8(t).ok())
+        .and_then(|t| t.parse().ok())
+        .ok_or(PbmError::Malformed)
+}
@@ -0,0 +1,202 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Host side simulator for Kywy games, requires the `std` feature.
+//! Provides a display with the same geometry and drawing API as `KywyDisplay`
+//! that dumps frames to PBM/PNG files or renders them in the terminal, plus a
+//! scripted button source standing in for the button channel. The `snapshot`
+//! module compares rendered frames against reference images in tests.
+
+pub mod buttons;
+pub mod image;
+pub mod snapshot;
+
+use crate::framebuffer::{DisplayRotation, Framebuffer, HEIGHT, WIDTH};
+use embedded_graphics::{
+    Pixel,
+    draw_target::DrawTarget,
+    geometry::Point,
+    image::GetPixel,
+    pixelcolor::BinaryColor,
+    prelude::{OriginDimensions, Size},
+    primitives::Rectangle,
+};
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+pub use buttons::ScriptedButtons;
+
+/// Stand in for `KywyDisplay` on a workstation.
+pub struct SimulatorDisplay {
+    framebuffer: Framebuffer,
+    frame_dir: Option<PathBuf>,
+    terminal: bool,
+    frame: u32,
+}
+
+impl Default for SimulatorDisplay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimulatorDisplay {
+    /// Creates a display that only keeps frames in memory.
+    pub fn new() -> Self {
+        Self {
+            framebuffer: Framebuffer::new(),
+            frame_dir: None,
+            terminal: false,
+            frame: 0,
+        }
+    }
+
+    /// Saves every presented frame as `frame_NNNNN.pbm` in `dir`.
+    pub fn with_frame_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.frame_dir = Some(dir.into());
+        self
+    }
+
+    /// Draws every presented frame to stdout with Unicode half blocks.
+    pub fn with_terminal(mut self, enable: bool) -> Self {
+        self.terminal = enable;
+        self
+    }
+
+    /// Presents the current buffer, the simulator counterpart of `write_display`.
+    pub async fn write_display(&mut self) -> io::Result<()> {
+        if let Some(dir) = &self.frame_dir {
+            fs::create_dir_all(dir)?;
+            let path = dir.join(format!("frame_{:05}.pbm", self.frame));
+            fs::write(path, image::encode_pbm(&self.framebuffer))?;
+        }
+
+        if self.terminal {
+            let mut out = io::stdout().lock();
+            // move the cursor home so frames animate in place
+            write!(out, "\x1b[H{}", self.to_terminal_string())?;
+            out.flush()?;
+        }
+
+        let lines = self.framebuffer.dirty_lines();
+        self.framebuffer.clear_dirty(lines);
+        self.frame += 1;
+        Ok(())
+    }
+
+    /// Number of frames presented so far.
+    pub fn frame_count(&self) -> u32 {
+        self.frame
+    }
+
+    pub fn save_pbm(&self, path: impl Into<PathBuf>) -> io::Result<()> {
+        fs::write(path.into(), image::encode_pbm(&self.framebuffer))
+    }
+
+    pub fn save_png(&self, path: impl Into<PathBuf>) -> io::Result<()> {
+        let file = fs::File::create(path.into())?;
+        image::write_png(&self.framebuffer, io::BufWriter::new(file))
+    }
+
+    /// Renders the buffer as text, two pixel rows per line of output.
+    /// On (white) pixels are drawn as filled blocks. Like saved frames the
+    /// output is in panel orientation, whatever the rotation.
+    pub fn to_terminal_string(&self) -> String {
+        let mut out = String::with_capacity((WIDTH * 3 + 1) * HEIGHT / 2);
+        for y in (0..HEIGHT).step_by(2) {
+            for x in 0..WIDTH {
+                let top = self
+                    .framebuffer
+                    .get_raw_pixel(x, y)
+                    .is_some_and(|c| c.is_on());
+                let bottom = self
+                    .framebuffer
+                    .get_raw_pixel(x, y + 1)
+                    .is_some_and(|c| c.is_on());
+                out.push(match (top, bottom) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn clear_buffer(&mut self, color: BinaryColor) {
+        self.framebuffer.fill(color);
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: BinaryColor) {
+        self.framebuffer.set_pixel(x, y, color);
+    }
+
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.framebuffer
+    }
+
+    pub fn framebuffer_mut(&mut self) -> &mut Framebuffer {
+        &mut self.framebuffer
+    }
+
+    pub fn set_rotation(&mut self, rotation: DisplayRotation) {
+        self.framebuffer.set_rotation(rotation);
+    }
+
+    pub fn set_mirror(&mut self, horizontal: bool, vertical: bool) {
+        self.framebuffer.set_mirror(horizontal, vertical);
+    }
+
+    pub fn height(&self) -> usize {
+        self.framebuffer.height()
+    }
+
+    pub fn width(&self) -> usize {
+        self.framebuffer.width()
+    }
+}
+
+impl OriginDimensions for SimulatorDisplay {
+    fn size(&self) -> Size {
+        self.framebuffer.size()
+    }
+}
+
+impl GetPixel for SimulatorDisplay {
+    type Color = BinaryColor;
+
+    fn pixel(&self, p: Point) -> Option<Self::Color> {
+        self.framebuffer.pixel(p)
+    }
+}
+
+impl DrawTarget for SimulatorDisplay {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.framebuffer.draw_iter(pixels)
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        self.framebuffer.fill_contiguous(area, colors)
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.framebuffer.fill_solid(area, color)
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.framebuffer.clear(color)
+    }
+}
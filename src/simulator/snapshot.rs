@@ -0,0 +1,167 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Golden image snapshot testing for rendered frames.
+//!
+//! References live in `tests/snapshots/<name>.pbm` next to the crate's
+//! `Cargo.toml`. When a frame does not match, the rendered frame and a diff
+//! image (differing pixels in black) are written to `target/snapshots/`.
+//! Run the tests with `KYWY_UPDATE_SNAPSHOTS=1` to write new references.
+
+use super::image::{PbmError, decode_pbm, encode_pbm};
+use crate::framebuffer::{Framebuffer, HEIGHT, WIDTH};
+use core::fmt;
+use embedded_graphics::pixelcolor::BinaryColor;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const UPDATE_ENV: &str = "KYWY_UPDATE_SNAPSHOTS";
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// No reference image exists yet, the rendered frame was saved to `actual`
+    Missing { reference: PathBuf, actual: PathBuf },
+    /// The frame differs from the reference in `pixels` pixels
+    Mismatch {
+        pixels: usize,
+        reference: PathBuf,
+        actual: PathBuf,
+        diff: PathBuf,
+    },
+    Io(io::Error),
+    Pbm(PbmError),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Missing { reference, actual } => write!(
+                f,
+                "missing reference {}, rendered frame saved to {} (set {}=1 to accept it)",
+                reference.display(),
+                actual.display(),
+                UPDATE_ENV
+            ),
+            SnapshotError::Mismatch {
+                pixels,
+                reference,
+                actual,
+                diff,
+            } => write!(
+                f,
+                "{} pixels differ from {}, see {} and {}",
+                pixels,
+                reference.display(),
+                actual.display(),
+                diff.display()
+            ),
+            SnapshotError::Io(e) => write!(f, "snapshot io error: {}", e),
+            SnapshotError::Pbm(e) => write!(f, "invalid reference image: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+impl From<PbmError> for SnapshotError {
+    fn from(e: PbmError) -> Self {
+        SnapshotError::Pbm(e)
+    }
+}
+
+/// Compares `framebuffer` with the reference image called `name`.
+pub fn compare_snapshot(name: &str, framebuffer: &Framebuffer) -> Result<(), SnapshotError> {
+    let root = manifest_dir();
+    let reference = root.join("tests").join("snapshots").join(format!("{}.pbm", name));
+    let out_dir = root.join("target").join("snapshots");
+    let actual = out_dir.join(format!("{}.actual.pbm", name));
+
+    if env::var_os(UPDATE_ENV).is_some() {
+        write_file(&reference, &encode_pbm(framebuffer))?;
+        return Ok(());
+    }
+
+    let expected = match fs::read(&reference) {
+        Ok(data) => decode_pbm(&data)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            write_file(&actual, &encode_pbm(framebuffer))?;
+            return Err(SnapshotError::Missing { reference, actual });
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let (pixels, diff_image) = diff(&expected, framebuffer);
+    if pixels == 0 {
+        return Ok(());
+    }
+
+    let diff = out_dir.join(format!("{}.diff.pbm", name));
+    write_file(&actual, &encode_pbm(framebuffer))?;
+    write_file(&diff, &encode_pbm(&diff_image))?;
+    Err(SnapshotError::Mismatch {
+        pixels,
+        reference,
+        actual,
+        diff,
+    })
+}
+
+/// Panics with a readable message if `framebuffer` does not match the reference `name`.
+#[track_caller]
+pub fn assert_snapshot(name: &str, framebuffer: &Framebuffer) {
+    if let Err(e) = compare_snapshot(name, framebuffer) {
+        panic!("snapshot `{}` failed: {}", name, e);
+    }
+}
+
+/// Renders a scene onto a white (On) buffer and checks it against the reference `name`.
+#[track_caller]
+pub fn assert_rendered<F>(name: &str, draw: F)
+where
+    F: FnOnce(&mut Framebuffer),
+{
+    let mut framebuffer = Framebuffer::new();
+    framebuffer.fill(BinaryColor::On);
+    draw(&mut framebuffer);
+    assert_snapshot(name, &framebuffer);
+}
+
+/// Counts differing pixels and builds an image with them in black on white.
+fn diff(expected: &Framebuffer, actual: &Framebuffer) -> (usize, Framebuffer) {
+    let mut image = Framebuffer::new();
+    image.fill(BinaryColor::On);
+
+    let mut count = 0;
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            if expected.get_raw_pixel(x, y) != actual.get_raw_pixel(x, y) {
+                image.set_raw_pixel(x, y, BinaryColor::Off);
+                count += 1;
+            }
+        }
+    }
+    (count, image)
+}
+
+fn manifest_dir() -> PathBuf {
+    // set by cargo for the crate whose tests are running
+    env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+}
+
+fn write_file(path: &Path, data: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, data)
+}
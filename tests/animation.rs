@@ -0,0 +1,234 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Animation timing for every play mode and the transitions between animation
+//! states, stepped with fixed time deltas.
+
+use embassy_time::Duration;
+use embedded_graphics::prelude::*;
+use kywy::engine::sprite::{
+    Animation, AnimationEvents, PlayMode, SpriteInstance, SpriteSheet, StateTransition,
+};
+
+const ELECTRIC: &[u8] = include_bytes!("../examples/Art Assets/monsters/electric.bmp");
+
+const FRAMES: &[(u32, u32)] = &[(0, 0), (1, 0), (2, 0)];
+const ATTACK: &[(u32, u32)] = &[(0, 1), (1, 1), (2, 1)];
+const WALK: &[(u32, u32)] = &[(0, 2), (1, 2)];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum State {
+    Idle,
+    Walk,
+    Attack,
+}
+
+const TRANSITIONS: &[StateTransition<State>] = &[
+    StateTransition::on_finish(State::Attack, State::Idle),
+    StateTransition::interrupt(State::Idle, State::Walk),
+    StateTransition::interrupt(State::Walk, State::Idle),
+    StateTransition::interrupt_any(State::Attack),
+];
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn sheet() -> SpriteSheet<'static> {
+    SpriteSheet::new(ELECTRIC, Size::new(64, 64)).unwrap()
+}
+
+fn animation<'a>(sheet: &'a SpriteSheet<'a>, mode: PlayMode) -> Animation<'a> {
+    Animation::new(sheet, FRAMES, false)
+        .with_mode(mode)
+        .with_frame_duration(ms(100))
+}
+
+/// Frames shown at the start and after each of `steps` updates of `dt`.
+fn frames(animation: &mut Animation<'_>, dt: Duration, steps: usize) -> Vec<usize> {
+    let mut shown = vec![animation.current_frame];
+    for _ in 0..steps {
+        animation.update(dt);
+        shown.push(animation.current_frame);
+    }
+    shown
+}
+
+#[test]
+fn once_stops_on_the_last_frame() {
+    let sheet = sheet();
+    let mut once = animation(&sheet, PlayMode::Once);
+    assert_eq!(frames(&mut once, ms(100), 2), [0, 1, 2]);
+    assert!(!once.is_finished());
+    assert_eq!(frames(&mut once, ms(100), 3), [2, 2, 2, 2]);
+    assert!(once.is_finished());
+}
+
+#[test]
+fn reverse_stops_on_the_first_frame() {
+    let sheet = sheet();
+    let mut reverse = animation(&sheet, PlayMode::Reverse);
+    assert_eq!(frames(&mut reverse, ms(100), 4), [2, 1, 0, 0, 0]);
+    assert!(reverse.is_finished());
+}
+
+#[test]
+fn loop_wraps() {
+    let sheet = sheet();
+    let mut looped = animation(&sheet, PlayMode::Loop);
+    assert_eq!(frames(&mut looped, ms(100), 7), [0, 1, 2, 0, 1, 2, 0, 1]);
+    assert!(!looped.is_finished());
+}
+
+#[test]
+fn ping_pong_does_not_repeat_the_ends() {
+    let sheet = sheet();
+    let mut ping_pong = animation(&sheet, PlayMode::PingPong);
+    assert_eq!(
+        frames(&mut ping_pong, ms(100), 8),
+        [0, 1, 2, 1, 0, 1, 2, 1, 0]
+    );
+    assert!(!ping_pong.is_finished());
+}
+
+#[test]
+fn per_frame_durations() {
+    let sheet = sheet();
+    let durations = [ms(50), ms(200), ms(100)];
+    let mut looped = animation(&sheet, PlayMode::Loop).with_durations(&durations);
+    // 10ms steps: 5 on the first frame, 20 on the second, 10 on the third
+    let shown = frames(&mut looped, ms(10), 39);
+    let count = |frame| shown.iter().filter(|&&f| f == frame).count();
+    assert_eq!(shown[..6], [0, 0, 0, 0, 0, 1]);
+    assert_eq!((count(0), count(1), count(2)), (5 + 5, 20, 10));
+
+    // a missing duration falls back to the frame duration
+    let mut once = animation(&sheet, PlayMode::Once).with_durations(&durations[..1]);
+    assert_eq!(frames(&mut once, ms(50), 3), [0, 1, 1, 2]);
+}
+
+#[test]
+fn long_stalls_skip_whole_passes() {
+    let sheet = sheet();
+    // a pass takes 300ms looped and 400ms there and back, stepping through a year
+    // of frames one at a time would take far too long
+    let year = ms(365 * 24 * 3600 * 1000);
+    let mut looped = animation(&sheet, PlayMode::Loop);
+    looped.update(ms(300) * 100_000_000 + ms(150));
+    assert_eq!(looped.current_frame, 1);
+    looped.update(year);
+    looped.update(ms(50));
+    assert_eq!(looped.current_frame, 2);
+
+    let mut ping_pong = animation(&sheet, PlayMode::PingPong);
+    ping_pong.update(ms(400) * 100_000_000 + ms(350));
+    assert_eq!(ping_pong.current_frame, 1);
+    // on the way back
+    ping_pong.update(ms(100));
+    assert_eq!(ping_pong.current_frame, 0);
+
+    let mut once = animation(&sheet, PlayMode::Once);
+    once.update(year);
+    assert!(once.is_finished());
+    assert_eq!(once.current_frame, 2);
+}
+
+/// Idle and walk loop, attack plays once, every frame shows for 100ms.
+fn monster<'a>(sheet: &'a SpriteSheet<'a>) -> SpriteInstance<'a, State> {
+    let states = [
+        (State::Idle, animation(sheet, PlayMode::Loop)),
+        (State::Walk, Animation::new(sheet, WALK, true)),
+        (State::Attack, Animation::new(sheet, ATTACK, false)),
+    ];
+    let states: heapless::Vec<_, 4> = states
+        .into_iter()
+        .map(|(state, animation)| (state, animation.with_frame_duration(ms(100))))
+        .collect();
+    SpriteInstance::with_states(states, Point::zero()).with_transitions(TRANSITIONS)
+}
+
+#[test]
+fn finish_transition_waits_for_the_end() {
+    let sheet = sheet();
+    let mut monster = monster(&sheet);
+    assert!(monster.request(State::Attack));
+    // nothing interrupts an attack, it has to play out
+    assert!(!monster.request(State::Walk));
+    assert!(!monster.request(State::Idle));
+    for frame in [1, 2] {
+        monster.update(ms(100));
+        assert_eq!(monster.state(), State::Attack);
+        assert_eq!(monster.current().current_frame, frame);
+    }
+    monster.update(ms(99));
+    assert_eq!(monster.state(), State::Attack);
+    // the tick the last frame ends starts idle from its first frame
+    monster.update(ms(1));
+    assert_eq!(monster.state(), State::Idle);
+    assert_eq!(monster.current().current_frame, 0);
+    assert!(!monster.current().is_finished());
+}
+
+#[test]
+fn interrupt_switches_on_the_same_tick() {
+    let sheet = sheet();
+    let mut monster = monster(&sheet);
+    monster.update(ms(150));
+    assert_eq!(monster.current().current_frame, 1);
+
+    assert!(monster.request(State::Walk));
+    assert_eq!(monster.state(), State::Walk);
+    assert_eq!(monster.current().current_frame_loc(), WALK[0]);
+    // walk starts fresh, idle's time on its frame is not carried over
+    monster.update(ms(99));
+    assert_eq!(monster.current().current_frame, 0);
+    monster.update(ms(1));
+    assert_eq!(monster.current().current_frame, 1);
+
+    // requesting the playing state keeps it going instead of restarting it
+    assert!(monster.request(State::Walk));
+    assert_eq!(monster.current().current_frame, 1);
+
+    // catch all rules apply to every state
+    assert!(monster.request(State::Attack));
+    assert_eq!(monster.state(), State::Attack);
+    assert_eq!(monster.current().current_frame_loc(), ATTACK[0]);
+}
+
+const STEP: AnimationEvents = AnimationEvents::event(0);
+const HIT: AnimationEvents = AnimationEvents::event(31);
+const EVENTS: &[AnimationEvents] = &[STEP, AnimationEvents::NONE, HIT];
+
+#[test]
+fn events_fire_once_when_their_frame_starts() {
+    let sheet = sheet();
+    let mut looped = animation(&sheet, PlayMode::Loop).with_events(EVENTS);
+    let none = AnimationEvents::NONE;
+    // the first frame counts as started
+    assert_eq!(looped.update(ms(0)), STEP);
+    assert_eq!(looped.update(ms(50)), none);
+    assert_eq!(looped.update(ms(50)), none);
+    assert_eq!(looped.update(ms(100)), HIT);
+    assert_eq!(looped.update(ms(99)), none);
+    // wrapping around starts the first frame again
+    assert_eq!(looped.update(ms(1)), STEP);
+    // frames skipped over in one update still report their events
+    assert_eq!(looped.update(ms(250)), HIT);
+    assert_eq!(looped.update(ms(100)), STEP);
+    assert_eq!(looped.update(ms(300) * 1000), STEP | HIT);
+
+    // a finished animation does not start its last frame again
+    let mut once = animation(&sheet, PlayMode::Once).with_events(EVENTS);
+    assert_eq!(once.update(ms(200)), STEP | HIT);
+    assert_eq!(once.update(ms(1000)), none);
+    assert!(once.is_finished());
+    once.reset();
+    assert_eq!(once.update(ms(0)), STEP);
+}
+
+#[test]
+#[should_panic(expected = "animation event ids go from 0 to 31")]
+fn event_ids_stop_at_31() {
+    let id = std::hint::black_box(32);
+    AnimationEvents::event(id);
+}
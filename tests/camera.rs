@@ -0,0 +1,235 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Camera movement within bounds and deadzone, shake, and drawing through a view.
+
+use embassy_time::Duration;
+use embedded_graphics::{
+    Pixel, image::GetPixel, pixelcolor::BinaryColor, prelude::*, primitives::Rectangle,
+};
+use kywy::engine::camera::Camera;
+use kywy::framebuffer::Framebuffer;
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+fn rect(x: i32, y: i32, width: u32, height: u32) -> Rectangle {
+    Rectangle::new(Point::new(x, y), Size::new(width, height))
+}
+
+/// Positions of the On pixels.
+fn drawn(fb: &Framebuffer) -> Vec<Point> {
+    fb.bounding_box()
+        .points()
+        .filter(|&p| fb.pixel(p) == Some(BinaryColor::On))
+        .collect()
+}
+
+fn blank() -> Framebuffer {
+    let mut fb = Framebuffer::new();
+    fb.fill(BinaryColor::Off);
+    fb
+}
+
+#[test]
+fn bounds_keep_the_view_inside() {
+    let mut camera = Camera::default().with_bounds(rect(-20, 10, 512, 400));
+    assert_eq!(camera.position, Point::new(0, 10));
+    camera.set_position(Point::new(-100, 0));
+    assert_eq!(camera.position, Point::new(-20, 10));
+    camera.set_position(Point::new(1000, 1000));
+    assert_eq!(camera.position, Point::new(-20 + 512 - 144, 10 + 400 - 168));
+    camera.move_by(-30, -40);
+    assert_eq!(
+        camera.position,
+        Point::new(-20 + 512 - 144 - 30, 10 + 400 - 168 - 40)
+    );
+    // looking at a corner stops at the edge
+    camera.look_at(Point::new(0, 20));
+    assert_eq!(camera.position, Point::new(-20, 10));
+    assert!(camera.is_visible(&rect(-20, 10, 1, 1)));
+    assert!(!camera.is_visible(&rect(-21, 10, 1, 1)));
+
+    // shrinking the bounds or growing the view moves the camera back in
+    camera.set_position(Point::new(300, 200));
+    camera.set_bounds(Some(rect(0, 0, 320, 240)));
+    assert_eq!(camera.position, Point::new(320 - 144, 240 - 168));
+    camera.set_size(Size::new(168, 144));
+    assert_eq!(camera.position, Point::new(320 - 168, 240 - 168));
+}
+
+#[test]
+fn small_bounds_are_centered() {
+    // narrower than the view but taller
+    let mut camera = Camera::default().with_bounds(rect(20, 0, 100, 400));
+    camera.set_position(Point::new(500, 50));
+    assert_eq!(camera.position, Point::new(20 - (144 - 100) / 2, 50));
+    camera.look_at(Point::new(-500, 1000));
+    assert_eq!(camera.position, Point::new(-2, 400 - 168));
+
+    // smaller both ways, nothing moves the view
+    let mut camera = Camera::default().with_bounds(rect(0, 0, 64, 64));
+    let centered = Point::new(-40, -52);
+    assert_eq!(camera.position, centered);
+    camera.follow(Point::new(300, -300));
+    assert_eq!(camera.position, centered);
+
+    // exactly the size of the view
+    let camera = Camera::default().with_bounds(rect(5, 6, 144, 168));
+    assert_eq!(camera.position, Point::new(5, 6));
+}
+
+#[test]
+fn deadzone_lets_the_target_move() {
+    let start = Point::new(100, 100);
+    let mut camera = Camera::default().with_deadzone(Size::new(40, 30));
+    camera.set_position(start);
+    // the deadzone is centered on the screen center (172, 184)
+    let zone = rect(153, 170, 40, 30);
+    for target in zone.points() {
+        camera.follow(target);
+        assert_eq!(camera.position, start, "{target}");
+    }
+
+    // outside it the camera moves by exactly the overshoot
+    for overshoot in [1, 2, 7, 50] {
+        let mut right = camera;
+        right.follow(Point::new(192 + overshoot, 184));
+        assert_eq!(right.position, start + Point::new(overshoot, 0));
+        let mut up = camera;
+        up.follow(Point::new(172, 170 - overshoot));
+        assert_eq!(up.position, start + Point::new(0, -overshoot));
+    }
+    camera.follow(Point::new(140, 210));
+    assert_eq!(camera.position, start + Point::new(-13, 11));
+    // the target is on the edge of the deadzone now, so it stays put
+    camera.follow(Point::new(140, 210));
+    assert_eq!(camera.position, start + Point::new(-13, 11));
+
+    // without a deadzone the target is kept centered
+    let mut camera = Camera::default();
+    camera.follow(Point::new(500, 300));
+    assert_eq!(camera.position, Point::new(500 - 72, 300 - 84));
+}
+
+/// Shake offsets after each of `steps` updates of `dt`.
+fn shake(camera: &mut Camera, dt: Duration, steps: usize) -> Vec<Point> {
+    (0..steps)
+        .map(|_| {
+            camera.update(dt);
+            camera.offset() - camera.position
+        })
+        .collect()
+}
+
+#[test]
+fn shake_calms_down_and_stops() {
+    let mut camera = Camera::default();
+    camera.set_position(Point::new(30, 40));
+    camera.shake(8, ms(200));
+    assert!(camera.is_shaking());
+    let offsets = shake(&mut camera, ms(10), 19);
+    for (i, offset) in offsets.iter().enumerate() {
+        // the allowed range shrinks with the time left
+        let range = 8 * (190 - 10 * i as i32) / 200;
+        assert!(
+            offset.x.abs() <= range && offset.y.abs() <= range,
+            "{i}: {offset}"
+        );
+    }
+    assert!(offsets.iter().any(|offset| *offset != Point::zero()));
+
+    camera.update(ms(10));
+    assert!(!camera.is_shaking());
+    assert_eq!(camera.offset(), Point::new(30, 40));
+    assert_eq!(camera.viewport(), rect(30, 40, 144, 168));
+    // a long frame ends the shake at once
+    camera.shake(8, ms(200));
+    camera.update(ms(1000));
+    assert!(!camera.is_shaking());
+    assert_eq!(camera.offset(), Point::new(30, 40));
+}
+
+#[test]
+fn shake_follows_the_seed() {
+    let shaken = |seed| {
+        let mut camera = Camera::default().with_shake_seed(seed);
+        camera.shake(6, ms(500));
+        shake(&mut camera, ms(16), 20)
+    };
+    assert_eq!(shaken(7), shaken(7));
+    assert_ne!(shaken(7), shaken(8));
+    // zero would get stuck, it still shakes
+    assert!(shaken(0).iter().any(|offset| *offset != Point::zero()));
+}
+
+#[test]
+fn view_draws_in_world_coordinates() {
+    let mut camera = Camera::default();
+    camera.set_position(Point::new(100, 50));
+    let mut fb = blank();
+    let mut view = camera.view(&mut fb);
+    assert_eq!(view.bounding_box(), camera.viewport());
+
+    let on = BinaryColor::On;
+    view.draw_iter([
+        Pixel(Point::new(110, 60), on),
+        // just off each side
+        Pixel(Point::new(99, 60), on),
+        Pixel(Point::new(244, 60), on),
+        Pixel(Point::new(110, 49), on),
+        Pixel(Point::new(110, 218), on),
+    ])
+    .unwrap();
+    assert_eq!(view.pixel(Point::new(110, 60)), Some(on));
+    assert_eq!(view.pixel(Point::new(99, 60)), None);
+    assert_eq!(drawn(&fb), [Point::new(10, 10)]);
+
+    // areas across the edge are cut to the screen
+    let mut fb = blank();
+    let mut view = camera.view(&mut fb);
+    view.fill_solid(&rect(90, 40, 20, 30), on).unwrap();
+    assert_eq!(drawn(&fb), rect(0, 0, 10, 20).points().collect::<Vec<_>>());
+
+    let mut fb = blank();
+    let mut view = camera.view(&mut fb);
+    let area = rect(240, 210, 8, 12);
+    let checkers = area
+        .points()
+        .map(|p| BinaryColor::from((p.x + p.y) % 2 == 0));
+    view.fill_contiguous(&area, checkers).unwrap();
+    let expected: Vec<_> = rect(140, 160, 4, 8)
+        .points()
+        .filter(|p| (p.x + p.y) % 2 == 0)
+        .collect();
+    assert_eq!(drawn(&fb), expected);
+}
+
+#[test]
+fn small_camera_clips_to_its_size() {
+    let mut camera = Camera::new(Size::new(50, 40));
+    camera.set_position(Point::new(-10, -10));
+    let mut fb = blank();
+    let mut view = camera.view(&mut fb);
+    assert_eq!(view.bounding_box(), rect(-10, -10, 50, 40));
+    view.fill_solid(&rect(-100, -100, 500, 500), BinaryColor::On)
+        .unwrap();
+    view.clear(BinaryColor::On).unwrap();
+    assert_eq!(drawn(&fb), rect(0, 0, 50, 40).points().collect::<Vec<_>>());
+
+    // shake moves the drawing but not the area of the screen it covers
+    camera.shake(3, ms(100));
+    camera.update(ms(10));
+    let mut fb = blank();
+    let shaken = camera.offset();
+    let mut view = camera.view(&mut fb);
+    view.draw_iter([Pixel(shaken + Point::new(5, 5), BinaryColor::On)])
+        .unwrap();
+    assert_eq!(
+        camera.to_screen(shaken + Point::new(5, 5)),
+        Point::new(5, 5)
+    );
+    assert_eq!(camera.to_world(Point::new(5, 5)), shaken + Point::new(5, 5));
+    assert_eq!(drawn(&fb), [Point::new(5, 5)]);
+}
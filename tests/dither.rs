@@ -0,0 +1,309 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Dithered output checked against straightforward reference implementations.
+
+use embedded_graphics::image::GetPixel;
+use embedded_graphics::{
+    Pixel,
+    pixelcolor::{BinaryColor, Gray8},
+    prelude::*,
+    primitives::Rectangle,
+};
+use kywy::dither::{DitherMode, Dithered};
+use kywy::framebuffer::Framebuffer;
+
+const BAYER_4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+const BAYER_8: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// Error diffusion only covers this many columns, the rest are thresholded.
+const MAX_DIFFUSION_WIDTH: usize = 256;
+
+/// Black and white image that ignores pixels outside of it.
+struct Canvas {
+    size: Size,
+    pixels: Vec<Option<BinaryColor>>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            size: Size::new(width, height),
+            pixels: vec![None; (width * height) as usize],
+        }
+    }
+
+    fn get(&self, p: Point) -> Option<BinaryColor> {
+        self.pixels[(p.y as u32 * self.size.width + p.x as u32) as usize]
+    }
+}
+
+impl OriginDimensions for Canvas {
+    fn size(&self) -> Size {
+        self.size
+    }
+}
+
+impl DrawTarget for Canvas {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<BinaryColor>>,
+    {
+        for Pixel(p, color) in pixels {
+            if self.bounding_box().contains(p) {
+                self.pixels[(p.y as u32 * self.size.width + p.x as u32) as usize] = Some(color);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Gray levels covering the whole range with some texture.
+fn luma(x: u32, y: u32) -> u8 {
+    ((x * 37 + y * 91 + x * y * 13) % 256) as u8
+}
+
+fn grays(area: &Rectangle) -> impl Iterator<Item = Gray8> + '_ {
+    area.points().map(move |p| {
+        let local = p - area.top_left;
+        Gray8::new(luma(local.x as u32, local.y as u32))
+    })
+}
+
+/// Ordered dithering of one pixel, anchored to the target origin.
+fn ordered(mode: DitherMode, p: Point, luma: u8) -> BinaryColor {
+    let (x, y) = (p.x.rem_euclid(8) as usize, p.y.rem_euclid(8) as usize);
+    let on = match mode {
+        DitherMode::Threshold(level) => luma >= level,
+        DitherMode::Bayer4 => luma as u16 >= BAYER_4[y % 4][x % 4] as u16 * 16 + 8,
+        DitherMode::Bayer8 | DitherMode::FloydSteinberg => {
+            luma as u16 >= BAYER_8[y][x] as u16 * 4 + 2
+        }
+    };
+    BinaryColor::from(on)
+}
+
+/// Textbook Floyd–Steinberg over a whole area, in area coordinates.
+fn floyd_steinberg(width: usize, height: usize, luma: impl Fn(usize, usize) -> u8) -> Vec<bool> {
+    let mut error = vec![vec![0i16; width + 1]; height + 1];
+    let mut out = vec![false; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            if x >= MAX_DIFFUSION_WIDTH {
+                out[y * width + x] = luma(x, y) >= 128;
+                continue;
+            }
+            let value = luma(x, y) as i16 + error[y][x];
+            let on = value >= 128;
+            let e = value - if on { 255 } else { 0 };
+            if x + 1 < width.min(MAX_DIFFUSION_WIDTH) {
+                error[y][x + 1] += e * 7 / 16;
+                error[y + 1][x + 1] += e / 16;
+            }
+            if x > 0 {
+                error[y + 1][x - 1] += e * 3 / 16;
+            }
+            error[y + 1][x] += e * 5 / 16;
+            out[y * width + x] = on;
+        }
+    }
+    out
+}
+
+fn expected(mode: DitherMode, area: &Rectangle) -> Vec<(Point, BinaryColor)> {
+    let (width, height) = (area.size.width as usize, area.size.height as usize);
+    let diffused = floyd_steinberg(width, height, |x, y| luma(x as u32, y as u32));
+    area.points()
+        .enumerate()
+        .map(|(i, p)| {
+            let local = p - area.top_left;
+            let color = match mode {
+                DitherMode::FloydSteinberg => BinaryColor::from(diffused[i]),
+                mode => ordered(mode, p, luma(local.x as u32, local.y as u32)),
+            };
+            (p, color)
+        })
+        .collect()
+}
+
+const MODES: [DitherMode; 5] = [
+    DitherMode::Threshold(0),
+    DitherMode::Threshold(100),
+    DitherMode::Bayer4,
+    DitherMode::Bayer8,
+    DitherMode::FloydSteinberg,
+];
+
+fn check(canvas: &Canvas, mode: DitherMode, area: &Rectangle) {
+    let mut drawn = 0;
+    for (p, color) in expected(mode, area) {
+        if canvas.bounding_box().contains(p) {
+            assert_eq!(canvas.get(p), Some(color), "{mode:?} {area:?} at {p}");
+            drawn += 1;
+        }
+    }
+    let set = canvas.pixels.iter().filter(|c| c.is_some()).count();
+    assert_eq!(set, drawn, "{mode:?} {area:?} drew outside of the area");
+}
+
+#[test]
+fn areas_match_reference() {
+    let areas = [
+        Rectangle::new(Point::new(0, 0), Size::new(16, 16)),
+        Rectangle::new(Point::new(3, 5), Size::new(21, 9)),
+        Rectangle::new(Point::new(7, 1), Size::new(1, 12)),
+    ];
+    for mode in MODES {
+        for area in areas {
+            let mut canvas = Canvas::new(40, 30);
+            Dithered::new(&mut canvas, mode)
+                .fill_contiguous(&area, grays(&area))
+                .unwrap();
+            check(&canvas, mode, &area);
+        }
+    }
+}
+
+#[test]
+fn clipped_areas_match_reference() {
+    // partly outside of the canvas, diffusion still runs over the hidden pixels
+    let areas = [
+        Rectangle::new(Point::new(-5, -3), Size::new(20, 11)),
+        Rectangle::new(Point::new(30, 25), Size::new(17, 9)),
+    ];
+    for mode in MODES {
+        for area in areas {
+            let mut canvas = Canvas::new(40, 30);
+            Dithered::new(&mut canvas, mode)
+                .fill_contiguous(&area, grays(&area))
+                .unwrap();
+            check(&canvas, mode, &area);
+        }
+    }
+}
+
+#[test]
+fn wide_areas_match_reference() {
+    for width in [MAX_DIFFUSION_WIDTH as u32, 257, 300] {
+        let area = Rectangle::new(Point::new(2, 1), Size::new(width, 6));
+        for mode in MODES {
+            let mut canvas = Canvas::new(320, 8);
+            Dithered::new(&mut canvas, mode)
+                .fill_contiguous(&area, grays(&area))
+                .unwrap();
+            check(&canvas, mode, &area);
+        }
+    }
+}
+
+#[test]
+fn floyd_steinberg_by_hand() {
+    // (0,0) 100 is off and pushes +43 right, +31 down, +6 down right
+    // (1,0) 143 is on and pushes -21 down left, -35 down
+    // (0,1) 110 is off and pushes +48 right, (1,1) 100 - 29 + 48 = 119 is off
+    let area = Rectangle::new(Point::zero(), Size::new(2, 2));
+    let mut canvas = Canvas::new(2, 2);
+    Dithered::new(&mut canvas, DitherMode::FloydSteinberg)
+        .fill_contiguous(&area, [Gray8::new(100); 4])
+        .unwrap();
+    let colors: Vec<_> = area.points().map(|p| canvas.get(p).unwrap()).collect();
+    assert_eq!(
+        colors,
+        [
+            BinaryColor::Off,
+            BinaryColor::On,
+            BinaryColor::Off,
+            BinaryColor::Off
+        ]
+    );
+}
+
+#[test]
+fn solid_levels() {
+    let area = Rectangle::new(Point::zero(), Size::new(16, 16));
+    for mode in MODES {
+        for (luma, color) in [(0, BinaryColor::Off), (255, BinaryColor::On)] {
+            if mode == DitherMode::Threshold(0) && luma == 0 {
+                continue;
+            }
+            let mut canvas = Canvas::new(16, 16);
+            Dithered::new(&mut canvas, mode)
+                .fill_contiguous(&area, area.points().map(|_| Gray8::new(luma)))
+                .unwrap();
+            assert!(
+                canvas.pixels.iter().all(|c| *c == Some(color)),
+                "{mode:?} {luma}"
+            );
+        }
+    }
+}
+
+#[test]
+fn half_gray_is_half_on() {
+    let area = Rectangle::new(Point::zero(), Size::new(16, 16));
+    for mode in [
+        DitherMode::Bayer4,
+        DitherMode::Bayer8,
+        DitherMode::FloydSteinberg,
+    ] {
+        let mut canvas = Canvas::new(16, 16);
+        Dithered::new(&mut canvas, mode)
+            .fill_contiguous(&area, area.points().map(|_| Gray8::new(128)))
+            .unwrap();
+        let on = canvas
+            .pixels
+            .iter()
+            .filter(|c| **c == Some(BinaryColor::On))
+            .count();
+        assert!((120..=136).contains(&on), "{mode:?} {on}");
+    }
+}
+
+#[test]
+fn single_pixels_use_ordered_dithering() {
+    let area = Rectangle::new(Point::new(1, 2), Size::new(12, 10));
+    for mode in MODES {
+        let mut canvas = Canvas::new(16, 16);
+        let pixels = area.points().zip(grays(&area)).map(|(p, c)| Pixel(p, c));
+        Dithered::new(&mut canvas, mode).draw_iter(pixels).unwrap();
+        for p in area.points() {
+            let local = p - area.top_left;
+            let luma = luma(local.x as u32, local.y as u32);
+            assert_eq!(
+                canvas.get(p),
+                Some(ordered(mode, p, luma)),
+                "{mode:?} at {p}"
+            );
+        }
+    }
+}
+
+#[test]
+fn clipped_on_framebuffer() {
+    // the framebuffer packs whole bytes, the clipped edges must still line up
+    let area = Rectangle::new(Point::new(-7, 150), Size::new(40, 30));
+    for mode in MODES {
+        let mut fb = Framebuffer::new();
+        Dithered::new(&mut fb, mode)
+            .fill_contiguous(&area, grays(&area))
+            .unwrap();
+        for (p, color) in expected(mode, &area) {
+            if fb.bounding_box().contains(p) {
+                assert_eq!(fb.pixel(p), Some(color), "{mode:?} at {p}");
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The byte wise fills must give the same pixels and dirty lines as drawing pixel by pixel.
+
+use embedded_graphics::{Pixel, pixelcolor::BinaryColor, prelude::*, primitives::Rectangle};
+use kywy::framebuffer::{DisplayRotation, Framebuffer, HEIGHT};
+
+const ROTATIONS: [DisplayRotation; 4] = [
+    DisplayRotation::Rotate0,
+    DisplayRotation::Rotate90,
+    DisplayRotation::Rotate180,
+    DisplayRotation::Rotate270,
+];
+
+/// Areas inside, across the edges of and completely outside the screen, at odd offsets.
+fn areas() -> Vec<Rectangle> {
+    [
+        (0, 0, 144, 168),
+        (3, 5, 1, 1),
+        (7, 2, 9, 13),
+        (8, 8, 16, 4),
+        (13, 21, 61, 40),
+        (-5, -3, 20, 11),
+        (130, 150, 50, 40),
+        (-10, 100, 200, 3),
+        (200, 200, 10, 10),
+        (5, 5, 0, 7),
+    ]
+    .into_iter()
+    .map(|(x, y, w, h)| Rectangle::new(Point::new(x, y), Size::new(w, h)))
+    .collect()
+}
+
+fn configured(rotation: DisplayRotation, mirror: (bool, bool)) -> Framebuffer {
+    let mut fb = Framebuffer::new();
+    fb.set_rotation(rotation);
+    fb.set_mirror(mirror.0, mirror.1);
+    // some existing contents, so unchanged and changed bits both happen
+    for y in 0..fb.height() {
+        for x in (y % 3..fb.width()).step_by(3) {
+            fb.set_pixel(x, y, BinaryColor::On);
+        }
+    }
+    fb.mark_clean();
+    fb
+}
+
+/// Pixels of the pattern in area order, as `fill_contiguous` expects them.
+fn pattern(area: &Rectangle) -> impl Iterator<Item = BinaryColor> {
+    let mut seed = 0x1234_5678u32;
+    area.points().map(move |_| {
+        seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        BinaryColor::from(seed >> 31 != 0)
+    })
+}
+
+fn assert_same(fast: &Framebuffer, slow: &Framebuffer, what: &str) {
+    assert_eq!(fast.as_bytes(), slow.as_bytes(), "{what}: pixels differ");
+    for y in 0..HEIGHT {
+        assert_eq!(
+            fast.is_line_dirty(y),
+            slow.is_line_dirty(y),
+            "{what}: dirty line {y} differs"
+        );
+    }
+}
+
+fn each_configuration(mut check: impl FnMut(Framebuffer, String)) {
+    for rotation in ROTATIONS {
+        for mirror in [(false, false), (true, false), (false, true), (true, true)] {
+            check(
+                configured(rotation, mirror),
+                format!("{rotation:?} {mirror:?}"),
+            );
+        }
+    }
+}
+
+#[test]
+fn fill_contiguous_matches_pixels() {
+    each_configuration(|base, name| {
+        for area in areas() {
+            let mut fast = base.clone();
+            let mut slow = base.clone();
+            fast.fill_contiguous(&area, pattern(&area)).unwrap();
+            let pixels = area.points().zip(pattern(&area)).map(|(p, c)| Pixel(p, c));
+            slow.draw_iter(pixels).unwrap();
+            assert_same(&fast, &slow, &format!("{name} {area:?}"));
+        }
+    });
+}
+
+#[test]
+fn fill_contiguous_with_short_iterator() {
+    let area = Rectangle::new(Point::new(3, 4), Size::new(20, 5));
+    let mut fast = configured(DisplayRotation::Rotate0, (false, false));
+    let mut slow = fast.clone();
+    fast.fill_contiguous(&area, pattern(&area).take(47))
+        .unwrap();
+    let pixels = area.points().zip(pattern(&area).take(47));
+    slow.draw_iter(pixels.map(|(p, c)| Pixel(p, c))).unwrap();
+    assert_same(&fast, &slow, "short iterator");
+}
+
+#[test]
+fn fill_solid_matches_pixels() {
+    each_configuration(|base, name| {
+        for area in areas() {
+            for color in [BinaryColor::On, BinaryColor::Off] {
+                let mut fast = base.clone();
+                let mut slow = base.clone();
+                fast.fill_solid(&area, color).unwrap();
+                slow.draw_iter(area.points().map(|p| Pixel(p, color)))
+                    .unwrap();
+                assert_same(&fast, &slow, &format!("{name} {area:?} {color:?}"));
+            }
+        }
+    });
+}
+
+#[test]
+fn clear_matches_pixels() {
+    each_configuration(|base, name| {
+        for color in [BinaryColor::On, BinaryColor::Off] {
+            let mut fast = base.clone();
+            let mut slow = base.clone();
+            fast.clear(color).unwrap();
+            let area = slow.bounding_box();
+            slow.draw_iter(area.points().map(|p| Pixel(p, color)))
+                .unwrap();
+            assert_same(&fast, &slow, &format!("{name} {color:?}"));
+        }
+    });
+}
+
+#[test]
+fn unchanged_fill_leaves_lines_clean() {
+    let mut fb = Framebuffer::new();
+    fb.clear(BinaryColor::On).unwrap();
+    fb.mark_clean();
+    let area = Rectangle::new(Point::new(10, 10), Size::new(30, 30));
+    fb.fill_solid(&area, BinaryColor::On).unwrap();
+    fb.fill_contiguous(&area, area.points().map(|_| BinaryColor::On))
+        .unwrap();
+    assert!(!fb.is_dirty());
+}
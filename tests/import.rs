@@ -0,0 +1,302 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Sprite and tilemap modules generated from the example descriptors and the
+//! Aseprite and Tiled files in `tests/fixtures/`.
+
+use kywy::import::{ImportError, generate, generate_map};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MONSTERS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/Art Assets/monsters");
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+/// Writes a descriptor for electric.bmp with one animation called `name`.
+fn descriptor(file: &str, name: &str, frames: &str) -> PathBuf {
+    let image = Path::new(MONSTERS).join("electric.bmp");
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(file);
+    let toml = format!(
+        "image = {:?}\nsprite_width = 64\nsprite_height = 64\n\n\
+         [[animation]]\nname = {name:?}\nframes = {frames}\n",
+        image.display().to_string()
+    );
+    fs::write(&path, toml).unwrap();
+    path
+}
+
+#[test]
+fn electric_descriptor() {
+    let source = generate(Path::new(MONSTERS).join("electric.toml")).unwrap();
+    assert!(source.starts_with("// Generated by kywy::import from"));
+    assert!(source.contains("pub mod electric {"));
+    assert!(source.contains("pub const SPRITE_SIZE: Size = Size::new(64, 64);"));
+    assert!(source.contains("pub const FRAMES: [Rectangle; 12] = ["));
+    assert!(source.contains("pub const ANIMATIONS: [(&str, AnimationData<'static>); 3] = ["));
+    assert!(source.contains(concat!(
+        "    pub const IDLE: AnimationData<'static> = AnimationData {\n",
+        "        frames: &[(0, 0), (1, 0), (2, 0)],\n",
+        "        durations: &[Duration::from_millis(150), Duration::from_millis(150), ",
+        "Duration::from_millis(150)],\n",
+        "        mode: PlayMode::PingPong,\n",
+    )));
+    assert!(source.contains("(\"left_trigger\", LEFT_TRIGGER),"));
+    assert!(source.contains("mode: PlayMode::Once,"));
+}
+
+#[test]
+fn animation_names_clash_with_constants() {
+    for (i, name) in ["frames", "Image", "mask", "sprite size", "ANIMATIONS"]
+        .into_iter()
+        .enumerate()
+    {
+        let path = descriptor(&format!("reserved{i}.toml"), name, "[[0, 0]]");
+        match generate(&path) {
+            Err(ImportError::Invalid(msg)) => assert!(msg.contains(name), "{msg}"),
+            other => panic!("`{name}` was accepted: {other:?}"),
+        }
+    }
+    // other names still work
+    let path = descriptor("allowed.toml", "frames left", "[[0, 0]]");
+    assert!(generate(&path).unwrap().contains("pub const FRAMES_LEFT:"));
+}
+
+#[test]
+fn animations_need_frames_and_a_name() {
+    let path = descriptor("empty.toml", "idle", "[]");
+    match generate(&path) {
+        Err(ImportError::Invalid(msg)) => assert!(msg.contains("`idle` has no frames"), "{msg}"),
+        other => panic!("empty animation was accepted: {other:?}"),
+    }
+    for (i, name) in ["", "-", "!?", "  "].into_iter().enumerate() {
+        let path = descriptor(&format!("symbols{i}.toml"), name, "[[0, 0]]");
+        match generate(&path) {
+            Err(ImportError::Invalid(msg)) => {
+                assert!(msg.contains("has no usable identifier"), "{msg}")
+            }
+            other => panic!("`{name}` was accepted: {other:?}"),
+        }
+    }
+}
+
+/// The generated constant for an animation.
+fn animation(name: &str, frames: &str, durations: &[u64], mode: &str) -> String {
+    let durations: Vec<_> = durations
+        .iter()
+        .map(|ms| format!("Duration::from_millis({ms})"))
+        .collect();
+    format!(
+        "    pub const {name}: AnimationData<'static> = AnimationData {{\n        \
+         frames: &[{frames}],\n        durations: &[{}],\n        mode: PlayMode::{mode},\n",
+        durations.join(", ")
+    )
+}
+
+#[test]
+fn aseprite_hash_with_tags() {
+    let source = generate(Path::new(FIXTURES).join("electric_hash.json")).unwrap();
+    assert!(source.contains("pub mod electric_hash {"));
+    assert!(source.contains("pub const SPRITE_SIZE: Size = Size::new(64, 64);"));
+    assert!(source.contains(concat!(
+        "    pub const FRAMES: [Rectangle; 6] = [\n",
+        "        Rectangle::new(Point::new(0, 0), SPRITE_SIZE),\n",
+        "        Rectangle::new(Point::new(64, 0), SPRITE_SIZE),\n",
+        "        Rectangle::new(Point::new(128, 0), SPRITE_SIZE),\n",
+        "        Rectangle::new(Point::new(0, 64), SPRITE_SIZE),\n",
+    )));
+    // each tag keeps the durations of its own frames
+    let expected = [
+        animation("IDLE", "(0, 0), (1, 0), (2, 0)", &[100, 150, 200], "Loop"),
+        animation("WALK_BACK", "(2, 1), (1, 1), (0, 1)", &[70, 90, 80], "Loop"),
+        animation(
+            "BOB",
+            "(0, 0), (1, 0), (2, 0)",
+            &[100, 150, 200],
+            "PingPong",
+        ),
+        animation(
+            "BOB_BACK",
+            "(2, 1), (1, 1), (0, 1)",
+            &[70, 90, 80],
+            "PingPong",
+        ),
+        animation("HIT", "(0, 1), (1, 1)", &[80, 90], "Once"),
+    ];
+    for animation in expected {
+        assert!(source.contains(&animation), "{animation}\nnot in\n{source}");
+    }
+    assert!(source.contains("pub const ANIMATIONS: [(&str, AnimationData<'static>); 5] = ["));
+    assert!(source.contains("(\"walk back\", WALK_BACK),"));
+}
+
+#[test]
+fn aseprite_array_without_tags() {
+    let source = generate(Path::new(FIXTURES).join("electric_array.json")).unwrap();
+    assert!(source.contains("pub mod electric_array {"));
+    assert!(source.contains("pub const FRAMES: [Rectangle; 2] = ["));
+    // without tags every frame plays in a loop
+    let all = animation("ALL", "(0, 2), (1, 2)", &[120, 60], "Loop");
+    assert!(source.contains(&all), "{source}");
+    assert!(source.contains("pub const ANIMATIONS: [(&str, AnimationData<'static>); 1] = ["));
+}
+
+/// Writes a 4x1 map using the fixture tileset with one CSV layer.
+fn tiled_map(file: &str, layer: &str, csv: &str) -> PathBuf {
+    let image = Path::new(FIXTURES).join("tiles.bmp");
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(file);
+    let tmx = format!(
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<map orientation=\"orthogonal\" width=\"4\" height=\"1\" ",
+            "tilewidth=\"16\" tileheight=\"16\" infinite=\"0\">\n",
+            " <tileset firstgid=\"1\" name=\"tiles\" tilewidth=\"16\" tileheight=\"16\">\n",
+            "  <image source={:?} width=\"64\" height=\"16\"/>\n",
+            " </tileset>\n",
+            " <layer name={:?} width=\"4\" height=\"1\">\n",
+            "  <data encoding=\"csv\">{}</data>\n",
+            " </layer>\n",
+            "</map>\n",
+        ),
+        image.display().to_string(),
+        layer,
+        csv
+    );
+    fs::write(&path, tmx).unwrap();
+    path
+}
+
+/// The generated module without the header and module name, which follow the file.
+fn map_body(file: &str) -> String {
+    let source = generate_map(Path::new(FIXTURES).join(file)).unwrap();
+    source.lines().skip(3).collect::<Vec<_>>().join("\n")
+}
+
+#[test]
+fn tiled_tmx() {
+    let source = generate_map(Path::new(FIXTURES).join("level.tmx")).unwrap();
+    assert!(source.contains("pub mod level {"));
+    assert!(source.contains("pub const TILE_SIZE: Size = Size::new(16, 16);"));
+    assert!(source.contains("pub const COLUMNS: u32 = 4;\n    pub const ROWS: u32 = 3;"));
+    assert!(source.contains(concat!(
+        "        (\"gravity\", Property::Float(9.5)),\n",
+        "        (\"night\", Property::Bool(false)),\n",
+        "        (\"title\", Property::Str(\"Level 1\")),\n",
+    )));
+    // the CSV layer, with the solid, platform and custom flags of its tiles
+    assert!(source.contains(concat!(
+        "    pub const GROUND: TilemapData<'static> = TilemapData {\n",
+        "        columns: COLUMNS,\n",
+        "        tiles: &[\n",
+        "            0, 0, 0, 4,\n",
+        "            0, 3, 0, 0,\n",
+        "            1, 1, 2, 2,\n",
+        "        ],\n",
+        "        flags: &[\n",
+        "            TileFlags(0), TileFlags(0), TileFlags(0), TileFlags(2),\n",
+        "            TileFlags(0), TileFlags(0), TileFlags(0), TileFlags(0),\n",
+        "            TileFlags(1), TileFlags(1), TileFlags(5), TileFlags(5),\n",
+        "        ],\n",
+    )));
+    // the Base64 layer only uses a tile without flags
+    assert!(source.contains(concat!(
+        "    pub const DECOR: TilemapData<'static> = TilemapData {\n",
+        "        columns: COLUMNS,\n",
+        "        tiles: &[\n",
+        "            0, 0, 3, 0,\n",
+        "            0, 0, 0, 0,\n",
+        "            0, 0, 0, 0,\n",
+        "        ],\n",
+        "        flags: &[],\n",
+    )));
+    assert!(!source.contains("SKY"));
+
+    // objects from inside a group, tile objects are moved to their top left corner
+    assert!(source.contains("pub const SPAWNS: [MapObject<'static>; 3] = ["));
+    assert!(source.contains(concat!(
+        "            name: \"player\",\n",
+        "            kind: \"spawn\",\n",
+        "            position: Point::new(24, 8),\n",
+        "            size: Size::new(0, 0),\n",
+        "            tile: 0,\n",
+        "            properties: &[(\"facing\", Property::Str(\"left\")), ",
+        "(\"lives\", Property::Int(3))],\n",
+    )));
+    assert!(source.contains(concat!(
+        "            kind: \"trigger\",\n",
+        "            position: Point::new(48, 16),\n",
+        "            size: Size::new(16, 16),\n",
+        "            tile: 0,\n",
+        "            properties: &[(\"delay\", Property::Float(0.5)), ",
+        "(\"locked\", Property::Bool(true))],\n",
+    )));
+    assert!(source.contains(concat!(
+        "            name: \"coin\",\n",
+        "            kind: \"\",\n",
+        "            position: Point::new(16, 16),\n",
+        "            size: Size::new(16, 16),\n",
+        "            tile: 3,\n",
+    )));
+    assert!(source.contains("        (\"ground\", GROUND),\n        (\"decor\", DECOR),\n"));
+    assert!(source.contains("        (\"spawns\", &SPAWNS),\n"));
+}
+
+#[test]
+fn tiled_external_tilesets_and_json() {
+    let embedded = map_body("level.tmx");
+    // the same map with its tileset in a .tsx file, and as JSON with a .tsj tileset
+    assert_eq!(map_body("level_tsx.tmx"), embedded);
+    assert_eq!(map_body("level_json.tmj"), embedded);
+}
+
+/// Error message for a generated map, panics if it was accepted.
+fn map_error(path: &Path) -> String {
+    match generate_map(path) {
+        Err(ImportError::Invalid(msg)) => msg,
+        other => panic!("{} was accepted: {other:?}", path.display()),
+    }
+}
+
+#[test]
+fn tiled_tiles_must_be_in_the_tileset() {
+    assert!(generate_map(tiled_map("in_range.tmx", "ground", "0,1,4,2")).is_ok());
+
+    let msg = map_error(&tiled_map("past_end.tmx", "ground", "0,1,6,2"));
+    assert!(
+        msg.starts_with("layer `ground` at column 2, row 0 uses tile id 5 but "),
+        "{msg}"
+    );
+    assert!(msg.ends_with("tiles.bmp only has 4 tiles"), "{msg}");
+
+    // Tiled sets the top bit for a horizontal flip
+    let flipped = 0x8000_0000u32 | 2;
+    let msg = map_error(&tiled_map(
+        "flipped.tmx",
+        "ground",
+        &format!("0,0,0,{flipped}"),
+    ));
+    assert_eq!(
+        msg,
+        "layer `ground` at column 3, row 0 is flipped or rotated, which the tilemap does not support"
+    );
+}
+
+#[test]
+fn tiled_layer_names() {
+    for (i, name) in ["", "!!!", " - "].into_iter().enumerate() {
+        let msg = map_error(&tiled_map(&format!("symbols{i}.tmx"), name, "0,0,0,0"));
+        assert_eq!(
+            msg,
+            format!("layer name `{name}` has no usable identifier, it needs a letter or digit")
+        );
+    }
+    for (i, name) in ["rows", "Tile size", "layers"].into_iter().enumerate() {
+        let msg = map_error(&tiled_map(&format!("reserved{i}.tmx"), name, "0,0,0,0"));
+        assert!(msg.contains("clashes with a generated constant"), "{msg}");
+    }
+    let path = tiled_map("layer_2.tmx", "2nd layer", "0,0,0,0");
+    assert!(
+        generate_map(path)
+            .unwrap()
+            .contains("pub const _2ND_LAYER: TilemapData")
+    );
+}
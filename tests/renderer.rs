@@ -0,0 +1,158 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Dirty rendering must leave the screen exactly as a full render would, while
+//! keeping changes far apart in separate areas.
+
+use embedded_graphics::{
+    image::GetPixel,
+    mono_font::{MonoTextStyle, ascii::FONT_6X10},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{Circle, PrimitiveStyle, Rectangle},
+};
+use kywy::engine::renderer::{MAX_DIRTY_RECTS, Renderer};
+use kywy::engine::sprite::{BlendMode, SpriteOptions, SpriteSheet};
+use kywy::framebuffer::Framebuffer;
+
+const ELECTRIC: &[u8] = include_bytes!("../examples/Art Assets/monsters/electric.bmp");
+
+const LABELS: [&str; 3] = ["Score 0", "Score 10", "Score 20"];
+
+/// Submits frame `t` of a scene where things move, change and come and go.
+fn scene<'a>(renderer: &mut Renderer<'a>, sheet: &'a SpriteSheet<'a>, t: u32) {
+    let black = PrimitiveStyle::with_fill(BinaryColor::Off);
+    let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
+
+    renderer
+        .rectangle(
+            0,
+            Rectangle::new(Point::new(20, 60), Size::new(100, 30)),
+            black,
+        )
+        .ok();
+    // a monster walking right over the rectangle, only its On pixels are drawn
+    let walker = SpriteOptions {
+        blend: BlendMode::TransparentOff,
+        ..SpriteOptions::default()
+    };
+    let frame = sheet.sprite(t % 3, 0).unwrap();
+    renderer
+        .sprite(1, frame, Point::new(-10 + 7 * t as i32, 40), walker)
+        .ok();
+    // one in the opposite corner that bobs up and down
+    let bob = sheet.sprite(1, 1).unwrap();
+    renderer
+        .sprite(
+            1,
+            bob,
+            Point::new(80, 100 + (t % 3) as i32),
+            SpriteOptions::default(),
+        )
+        .ok();
+    renderer
+        .text(2, LABELS[t as usize % 3], Point::new(2, 10), style)
+        .ok();
+    if t % 4 < 2 {
+        renderer
+            .circle(2, Circle::new(Point::new(120, 4), 12), black)
+            .ok();
+    }
+}
+
+#[test]
+fn dirty_render_matches_full_render() {
+    let sheet = SpriteSheet::new(ELECTRIC, Size::new(64, 64)).unwrap();
+    let mut renderer: Renderer = Renderer::new();
+    let mut fb = Framebuffer::new();
+    for t in 0..20 {
+        scene(&mut renderer, &sheet, t);
+        let dirty = renderer.render_dirty(&mut fb).unwrap();
+        assert!(dirty.len() <= MAX_DIRTY_RECTS);
+
+        let mut full = Framebuffer::new();
+        let mut reference: Renderer = Renderer::new();
+        scene(&mut reference, &sheet, t);
+        reference.render(&mut full).unwrap();
+        assert_eq!(fb.as_bytes(), full.as_bytes(), "frame {t}");
+    }
+}
+
+#[test]
+fn unchanged_frame_draws_nothing() {
+    let sheet = SpriteSheet::new(ELECTRIC, Size::new(64, 64)).unwrap();
+    let mut renderer: Renderer = Renderer::new();
+    let mut fb = Framebuffer::new();
+    scene(&mut renderer, &sheet, 0);
+    assert_eq!(
+        renderer.render_dirty(&mut fb).unwrap().as_slice(),
+        [fb.bounding_box()]
+    );
+    fb.mark_clean();
+    scene(&mut renderer, &sheet, 0);
+    assert!(renderer.render_dirty(&mut fb).unwrap().is_empty());
+    assert!(!fb.is_dirty());
+}
+
+#[test]
+fn far_apart_changes_stay_separate() {
+    let black = PrimitiveStyle::with_fill(BinaryColor::Off);
+    let square = |x, y| Rectangle::new(Point::new(x, y), Size::new(10, 10));
+    let mut renderer: Renderer = Renderer::new();
+    let mut fb = Framebuffer::new();
+    renderer.rectangle(0, square(5, 5), black).ok();
+    renderer.rectangle(0, square(120, 150), black).ok();
+    renderer.render_dirty(&mut fb).unwrap();
+    fb.mark_clean();
+
+    // both move a pixel to the right
+    renderer.rectangle(0, square(6, 5), black).ok();
+    renderer.rectangle(0, square(121, 150), black).ok();
+    let dirty = renderer.render_dirty(&mut fb).unwrap();
+    assert_eq!(dirty.len(), 2, "{dirty:?}");
+    assert!(dirty.contains(&Rectangle::new(Point::new(5, 5), Size::new(11, 10))));
+    assert!(dirty.contains(&Rectangle::new(Point::new(120, 150), Size::new(11, 10))));
+    // only the lines of the squares are sent
+    assert!(fb.is_line_dirty(5) && fb.is_line_dirty(159));
+    assert!(!fb.is_line_dirty(80));
+}
+
+#[test]
+fn close_changes_are_merged() {
+    let black = PrimitiveStyle::with_fill(BinaryColor::Off);
+    let square = |x| Rectangle::new(Point::new(x, 20), Size::new(10, 10));
+    let mut renderer: Renderer = Renderer::new();
+    let mut fb = Framebuffer::new();
+    renderer.render_dirty(&mut fb).unwrap();
+
+    // a few pixels apart, cheaper to redraw as one
+    renderer.rectangle(0, square(10), black).ok();
+    renderer.rectangle(0, square(24), black).ok();
+    let dirty = renderer.render_dirty(&mut fb).unwrap();
+    assert_eq!(
+        dirty.as_slice(),
+        [Rectangle::new(Point::new(10, 20), Size::new(24, 10))]
+    );
+}
+
+#[test]
+fn many_changes_share_the_list() {
+    let black = PrimitiveStyle::with_fill(BinaryColor::Off);
+    let mut renderer: Renderer = Renderer::new();
+    let mut fb = Framebuffer::new();
+    renderer.render_dirty(&mut fb).unwrap();
+
+    // a grid of squares too far apart to merge, more than the list holds
+    let squares = (0..4).flat_map(|y| (0..3).map(move |x| (x * 50, y * 45)));
+    for (x, y) in squares.clone() {
+        let square = Rectangle::new(Point::new(x, y), Size::new(4, 4));
+        renderer.rectangle(0, square, black).ok();
+    }
+    let dirty = renderer.render_dirty(&mut fb).unwrap();
+    assert_eq!(dirty.len(), MAX_DIRTY_RECTS);
+    for (x, y) in squares {
+        let p = Point::new(x, y);
+        assert!(dirty.iter().any(|area| area.contains(p)), "{p}");
+        assert_eq!(fb.pixel(p), Some(BinaryColor::Off), "{p}");
+    }
+}
@@ -0,0 +1,159 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Golden images of sprites and text, references are in `tests/snapshots/`.
+//! Run with `KYWY_UPDATE_SNAPSHOTS=1` after an intended change to the output.
+
+use embedded_graphics::{
+    mono_font::{MonoTextStyle, ascii::FONT_6X10},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    text::Text,
+};
+use kywy::engine::sprite::{Rotation, SpriteOptions, SpriteSheet};
+use kywy::framebuffer::DisplayRotation;
+use kywy::simulator::snapshot::assert_rendered;
+
+const ELECTRIC: &[u8] = include_bytes!("../examples/Art Assets/monsters/electric.bmp");
+
+/// Top left corners of a 2x2 grid of 64x64 sprites.
+const GRID: [Point; 4] = [
+    Point::new(4, 4),
+    Point::new(76, 4),
+    Point::new(4, 76),
+    Point::new(76, 76),
+];
+
+fn sheet() -> SpriteSheet<'static> {
+    SpriteSheet::new(ELECTRIC, Size::new(64, 64)).unwrap()
+}
+
+#[test]
+fn sprite_flips() {
+    let sheet = sheet();
+    let sprite = sheet.sprite(0, 0).unwrap();
+    assert_rendered("sprite_flips", |fb| {
+        let flips = [(false, false), (true, false), (false, true), (true, true)];
+        for (pos, (flip_x, flip_y)) in GRID.into_iter().zip(flips) {
+            let options = SpriteOptions {
+                flip_x,
+                flip_y,
+                ..SpriteOptions::default()
+            };
+            sprite.draw(fb, pos, options).unwrap();
+        }
+    });
+}
+
+#[test]
+fn sprite_rotations() {
+    let sheet = sheet();
+    let sprite = sheet.sprite(1, 0).unwrap();
+    assert_rendered("sprite_rotations", |fb| {
+        let rotations = [
+            Rotation::None,
+            Rotation::R90,
+            Rotation::R180,
+            Rotation::R270,
+        ];
+        for (pos, rotation) in GRID.into_iter().zip(rotations) {
+            let options = SpriteOptions {
+                rotation,
+                ..SpriteOptions::default()
+            };
+            sprite.draw(fb, pos, options).unwrap();
+        }
+    });
+}
+
+#[test]
+fn sprite_rotation_with_flip() {
+    let sheet = sheet();
+    let sprite = sheet.sprite(2, 1).unwrap();
+    assert_rendered("sprite_rotation_with_flip", |fb| {
+        let rotations = [
+            Rotation::None,
+            Rotation::R90,
+            Rotation::R180,
+            Rotation::R270,
+        ];
+        for (pos, rotation) in GRID.into_iter().zip(rotations) {
+            let options = SpriteOptions {
+                flip_x: true,
+                rotation,
+                ..SpriteOptions::default()
+            };
+            sprite.draw(fb, pos, options).unwrap();
+        }
+    });
+}
+
+#[test]
+fn sprite_scale_2() {
+    let sheet = sheet();
+    let sprite = sheet.sprite(0, 1).unwrap();
+    assert_rendered("sprite_scale_2", |fb| {
+        let options = SpriteOptions {
+            scale: 2,
+            ..SpriteOptions::default()
+        };
+        sprite.draw(fb, Point::new(8, 20), options).unwrap();
+    });
+}
+
+/// Sprites turned by 45 degrees, the whole rotated square has to be on screen.
+fn draw_at_45(name: &str, flip_x: bool) {
+    let sheet = sheet();
+    let sprite = sheet.sprite(1, 0).unwrap();
+    let options = SpriteOptions {
+        flip_x,
+        angle: 45,
+        ..SpriteOptions::default()
+    };
+    let pos = Point::new(40, 52);
+    assert_rendered(name, |fb| {
+        assert!(
+            fb.bounding_box()
+                .contains(sprite.bounding_box(pos, options).top_left)
+        );
+        sprite.draw(fb, pos, options).unwrap();
+    });
+}
+
+#[test]
+fn sprite_angle_45() {
+    draw_at_45("sprite_angle_45", false);
+}
+
+#[test]
+fn sprite_angle_45_with_flip() {
+    draw_at_45("sprite_angle_45_with_flip", true);
+}
+
+#[test]
+fn text() {
+    assert_rendered("text", |fb| {
+        let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
+        Text::new("Kywy 144x168", Point::new(4, 12), style)
+            .draw(fb)
+            .unwrap();
+        Text::new(
+            "The quick brown fox\njumps over the lazy\ndog 0123456789",
+            Point::new(4, 30),
+            style,
+        )
+        .draw(fb)
+        .unwrap();
+    });
+}
+
+#[test]
+fn text_landscape() {
+    assert_rendered("text_landscape", |fb| {
+        fb.set_rotation(DisplayRotation::Rotate90);
+        let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
+        Text::new("Landscape 168x144", Point::new(4, 12), style)
+            .draw(fb)
+            .unwrap();
+    });
+}
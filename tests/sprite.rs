@@ -0,0 +1,495 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Sprite drawing checked pixel by pixel against the sheet as tinybmp reads it, and
+//! collisions between small synthetic sheets.
+
+use embedded_graphics::{
+    image::GetPixel,
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{PointsIter, Rectangle},
+};
+use kywy::engine::sprite::{BlendMode, Rotation, SpriteOptions, SpriteSheet};
+use kywy::framebuffer::Framebuffer;
+use tinybmp::Bmp;
+
+const ELECTRIC: &[u8] = include_bytes!("../examples/Art Assets/monsters/electric.bmp");
+
+const ROTATIONS: [Rotation; 4] = [
+    Rotation::None,
+    Rotation::R90,
+    Rotation::R180,
+    Rotation::R270,
+];
+
+const BLENDS: [BlendMode; 3] = [
+    BlendMode::Opaque,
+    BlendMode::TransparentOff,
+    BlendMode::TransparentOn,
+];
+
+/// Layout of a generated BMP
+#[derive(Clone, Copy)]
+struct Format {
+    /// Rows stored top row first, BMPs are usually the other way around
+    top_down: bool,
+    /// Palette entry 0 is white instead of black
+    inverted: bool,
+}
+
+const BOTTOM_UP: Format = Format {
+    top_down: false,
+    inverted: false,
+};
+
+/// Encodes an uncompressed 1bpp BMP.
+fn bmp(width: u32, height: u32, format: Format, on: impl Fn(u32, u32) -> bool) -> Vec<u8> {
+    let bytes_per_row = width.div_ceil(32) as usize * 4;
+    let mut data = vec![0u8; bytes_per_row * height as usize];
+    for y in 0..height {
+        let row = if format.top_down { y } else { height - 1 - y } as usize;
+        for x in 0..width {
+            if on(x, y) != format.inverted {
+                data[row * bytes_per_row + x as usize / 8] |= 0x80 >> (x % 8);
+            }
+        }
+    }
+
+    let mut file = Vec::new();
+    let offset = 14 + 40 + 8;
+    file.extend_from_slice(b"BM");
+    file.extend_from_slice(&((offset + data.len()) as u32).to_le_bytes());
+    file.extend_from_slice(&[0; 4]);
+    file.extend_from_slice(&(offset as u32).to_le_bytes());
+    file.extend_from_slice(&40u32.to_le_bytes());
+    file.extend_from_slice(&(width as i32).to_le_bytes());
+    let height = if format.top_down {
+        -(height as i32)
+    } else {
+        height as i32
+    };
+    file.extend_from_slice(&height.to_le_bytes());
+    file.extend_from_slice(&1u16.to_le_bytes());
+    file.extend_from_slice(&1u16.to_le_bytes());
+    file.extend_from_slice(&0u32.to_le_bytes());
+    file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    file.extend_from_slice(&[0; 8]);
+    file.extend_from_slice(&2u32.to_le_bytes());
+    file.extend_from_slice(&0u32.to_le_bytes());
+    let (black, white) = ([0, 0, 0, 0], [0xFF, 0xFF, 0xFF, 0]);
+    let palette = if format.inverted {
+        [white, black]
+    } else {
+        [black, white]
+    };
+    file.extend(palette.concat());
+    file.extend(data);
+    file
+}
+
+/// Irregular pixels, so every flip and rotation looks different.
+fn noise(x: u32, y: u32) -> bool {
+    let h = x.wrapping_mul(0x9E37_79B1) ^ y.wrapping_mul(0x85EB_CA77);
+    (h ^ (h >> 15)).wrapping_mul(0x2C1B_3C6D) >> 31 != 0
+}
+
+/// What a sprite should look like at a point of its transformed frame, from the sheet
+/// read with tinybmp. `None` where nothing is drawn.
+fn expected(
+    sheet: &Bmp<'_, BinaryColor>,
+    mask: Option<&Bmp<'_, BinaryColor>>,
+    frame: (u32, u32),
+    size: Size,
+    options: &SpriteOptions,
+    (x, y): (u32, u32),
+) -> Option<BinaryColor> {
+    let scale = options.scale.max(1) as u32;
+    let (x, y) = (x / scale, y / scale);
+    let (w, h) = (size.width, size.height);
+    // flipped first, then rotated clockwise
+    let (fx, fy) = match options.rotation {
+        Rotation::None => (x, y),
+        Rotation::R90 => (y, h - 1 - x),
+        Rotation::R180 => (w - 1 - x, h - 1 - y),
+        Rotation::R270 => (w - 1 - y, x),
+    };
+    let fx = if options.flip_x { w - 1 - fx } else { fx };
+    let fy = if options.flip_y { h - 1 - fy } else { fy };
+
+    let p = Point::new((frame.0 * w + fx) as i32, (frame.1 * h + fy) as i32);
+    let color = sheet.pixel(p).unwrap();
+    let opaque = mask.is_none_or(|mask| mask.pixel(p) == Some(BinaryColor::On));
+    let keep = match options.blend {
+        BlendMode::TransparentOff => color.is_on(),
+        BlendMode::TransparentOn => color.is_off(),
+        _ => true,
+    };
+    (opaque && keep).then_some(color)
+}
+
+/// A background of stripes, so transparent pixels show.
+fn background() -> Framebuffer {
+    let mut fb = Framebuffer::new();
+    for y in 0..fb.height() {
+        for x in 0..fb.width() {
+            fb.set_pixel(x, y, BinaryColor::from((x + y) % 3 == 0));
+        }
+    }
+    fb
+}
+
+fn options() -> impl Iterator<Item = SpriteOptions> {
+    ROTATIONS.into_iter().flat_map(|rotation| {
+        [(false, false), (true, false), (false, true), (true, true)]
+            .into_iter()
+            .flat_map(move |(flip_x, flip_y)| {
+                BLENDS.into_iter().flat_map(move |blend| {
+                    [1, 2].into_iter().map(move |scale| SpriteOptions {
+                        flip_x,
+                        flip_y,
+                        rotation,
+                        blend,
+                        scale,
+                        ..SpriteOptions::default()
+                    })
+                })
+            })
+    })
+}
+
+/// Draws every frame with every option at `pos` and compares the result.
+fn check_sheet(
+    sheet: &SpriteSheet<'_>,
+    data: &[u8],
+    mask_data: Option<&[u8]>,
+    pos: Point,
+    what: &str,
+) {
+    let bmp = Bmp::<BinaryColor>::from_slice(data).unwrap();
+    let mask = mask_data.map(|data| Bmp::<BinaryColor>::from_slice(data).unwrap());
+    let count = sheet.sprite_count();
+    let size = sheet.sprite_size();
+    let before = background();
+
+    for frame in (0..count.height).flat_map(|y| (0..count.width).map(move |x| (x, y))) {
+        let sprite = sheet.sprite(frame.0, frame.1).unwrap();
+        for options in options() {
+            let mut fb = before.clone();
+            sprite.draw(&mut fb, pos, options).unwrap();
+
+            let area = sprite.bounding_box(pos, options);
+            // a margin around the sprite must be left alone
+            let screen = fb.bounding_box();
+            for p in area.offset(2).points().filter(|p| screen.contains(*p)) {
+                let local = area
+                    .contains(p)
+                    .then(|| ((p.x - pos.x) as u32, (p.y - pos.y) as u32));
+                let want = local
+                    .and_then(|local| expected(&bmp, mask.as_ref(), frame, size, &options, local))
+                    .or(before.pixel(p));
+                assert_eq!(
+                    fb.pixel(p),
+                    want,
+                    "{what}: frame {frame:?} {options:?} at {p}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn monster_sheet_matches_bmp() {
+    let sheet = SpriteSheet::new(ELECTRIC, Size::new(64, 64)).unwrap();
+    // one frame, every option on all twelve takes a while
+    let bmp = Bmp::<BinaryColor>::from_slice(ELECTRIC).unwrap();
+    let sprite = sheet.sprite(1, 0).unwrap();
+    let before = background();
+    for options in options().filter(|o| o.scale == 1) {
+        let mut fb = before.clone();
+        sprite.draw(&mut fb, Point::new(5, 9), options).unwrap();
+        for y in 0..64 {
+            for x in 0..64 {
+                let p = Point::new(5 + x as i32, 9 + y as i32);
+                let want = expected(&bmp, None, (1, 0), Size::new(64, 64), &options, (x, y))
+                    .or(before.pixel(p));
+                assert_eq!(fb.pixel(p), want, "{options:?} at {p}");
+            }
+        }
+    }
+}
+
+#[test]
+fn unaligned_frames_match_bmp() {
+    // 13 pixels wide, so frames start in the middle of bytes
+    for format in [
+        BOTTOM_UP,
+        Format {
+            top_down: true,
+            inverted: false,
+        },
+        Format {
+            top_down: false,
+            inverted: true,
+        },
+    ] {
+        let data = bmp(39, 18, format, noise);
+        let sheet = SpriteSheet::new(&data, Size::new(13, 9)).unwrap();
+        check_sheet(&sheet, &data, None, Point::new(3, 7), "unaligned");
+    }
+}
+
+#[test]
+fn odd_sizes_match_bmp() {
+    for (size, pos) in [
+        (Size::new(5, 7), Point::new(9, 4)),
+        (Size::new(96, 32), Point::new(30, 100)),
+    ] {
+        let data = bmp(size.width * 2, size.height * 2, BOTTOM_UP, noise);
+        let sheet = SpriteSheet::new(&data, size).unwrap();
+        check_sheet(&sheet, &data, None, pos, "odd size");
+
+        let mask = bmp(size.width * 2, size.height * 2, BOTTOM_UP, |x, y| {
+            (x + 2 * y) % 7 != 0
+        });
+        let sheet = SpriteSheet::new(&data, size)
+            .unwrap()
+            .with_mask(&mask)
+            .unwrap();
+        check_sheet(&sheet, &data, Some(&mask), pos, "odd size, masked");
+    }
+}
+
+#[test]
+fn large_frames_match_bmp() {
+    // 5120 bytes per frame and larger than the screen
+    let size = Size::new(256, 160);
+    let data = bmp(512, 160, BOTTOM_UP, noise);
+    let bmp = Bmp::<BinaryColor>::from_slice(&data).unwrap();
+    let sheet = SpriteSheet::new(&data, size).unwrap();
+
+    let before = background();
+    let pos = Point::new(-50, -3);
+    let sprite = sheet.sprite(1, 0).unwrap();
+    for options in options().filter(|o| o.scale == 1) {
+        let mut fb = before.clone();
+        sprite.draw(&mut fb, pos, options).unwrap();
+        let area = sprite.bounding_box(pos, options);
+        for p in fb.bounding_box().points() {
+            let local = area
+                .contains(p)
+                .then(|| ((p.x - pos.x) as u32, (p.y - pos.y) as u32));
+            let want = local
+                .and_then(|local| expected(&bmp, None, (1, 0), size, &options, local))
+                .or(before.pixel(p));
+            assert_eq!(fb.pixel(p), want, "{options:?} at {p}");
+        }
+    }
+}
+
+#[test]
+fn masked_frames_match_bmp() {
+    let data = bmp(39, 18, BOTTOM_UP, noise);
+    let mask = bmp(39, 18, BOTTOM_UP, |x, y| (x * 3 + y) % 5 != 0);
+    let sheet = SpriteSheet::new(&data, Size::new(13, 9))
+        .unwrap()
+        .with_mask(&mask)
+        .unwrap();
+    check_sheet(&sheet, &data, Some(&mask), Point::new(-4, 11), "masked");
+}
+
+/// The rotated sprite has to reach every edge of its bounding box without crossing it,
+/// so nothing is cut off and redraws of the box cover it.
+#[test]
+fn rotated_sprites_fill_their_bounding_box() {
+    let data = bmp(24, 10, BOTTOM_UP, |_, _| true);
+    let sheet = SpriteSheet::new(&data, Size::new(24, 10)).unwrap();
+    let sprite = sheet.sprite(0, 0).unwrap();
+    let pos = Point::new(50, 60);
+    for (angle, scale, flip_x) in (0..360).step_by(15).flat_map(|angle| {
+        [(1, false), (2, false), (1, true)].map(|(scale, flip_x)| (angle, scale, flip_x))
+    }) {
+        let options = SpriteOptions {
+            flip_x,
+            scale,
+            angle,
+            ..SpriteOptions::default()
+        };
+        let mut fb = Framebuffer::new();
+        fb.fill(BinaryColor::Off);
+        sprite.draw(&mut fb, pos, options).unwrap();
+        let drawn: Vec<Point> = fb
+            .bounding_box()
+            .points()
+            .filter(|&p| fb.pixel(p) == Some(BinaryColor::On))
+            .collect();
+
+        let area = sprite.bounding_box(pos, options);
+        let min = drawn
+            .iter()
+            .fold(area.bottom_right().unwrap(), |min, p| min.component_min(*p));
+        let max = drawn
+            .iter()
+            .fold(area.top_left, |max, p| max.component_max(*p));
+        let outside = drawn.iter().find(|p| !area.contains(**p));
+        assert_eq!(outside, None, "{options:?} drew outside {area:?}");
+        // the box only rounds up, by at most a pixel and a half on each side
+        let gaps = [min - area.top_left, area.bottom_right().unwrap() - max];
+        assert!(
+            gaps.iter().all(|gap| gap.x <= 2 && gap.y <= 2),
+            "{options:?} leaves {gaps:?} of {area:?} empty"
+        );
+        // and no corner is cut off, nearest neighbour sampling keeps the pixel count
+        let expected = 24 * 10 * (scale as usize).pow(2);
+        assert!(
+            drawn.len().abs_diff(expected) * 20 <= expected,
+            "{options:?} drew {} pixels of {expected}",
+            drawn.len()
+        );
+    }
+}
+
+/// Two 8x8 frames, the first only has its left column On and the second is all On.
+fn collision_sheet() -> Vec<u8> {
+    bmp(16, 8, BOTTOM_UP, |x, _| x == 0 || x >= 8)
+}
+
+/// Only On pixels are drawn, so Off pixels never collide.
+const SOLID_ON: SpriteOptions = SpriteOptions {
+    flip_x: false,
+    flip_y: false,
+    rotation: Rotation::None,
+    blend: BlendMode::TransparentOff,
+    scale: 1,
+    angle: 0,
+};
+
+#[test]
+fn transparent_pixels_do_not_collide() {
+    let data = collision_sheet();
+    let sheet = SpriteSheet::new(&data, Size::new(8, 8)).unwrap();
+    let line = sheet.sprite(0, 0).unwrap();
+    let block = sheet.sprite(1, 0).unwrap();
+    let at = |x| Point::new(x, 0);
+
+    // the boxes overlap, but the columns are four pixels apart
+    assert!(!line.collides_with(at(0), SOLID_ON, &line, at(4), SOLID_ON));
+    assert!(line.collides_with(at(0), SOLID_ON, &line, at(0), SOLID_ON));
+    // the line's column is in the block
+    assert!(line.collides_with(at(4), SOLID_ON, &block, at(0), SOLID_ON));
+    assert!(!line.collides_with(at(0), SOLID_ON, &block, at(1), SOLID_ON));
+    // opaque sprites without a mask are solid
+    let opaque = SpriteOptions::default();
+    assert!(line.collides_with(at(0), opaque, &line, at(4), opaque));
+    // touching edges is no overlap
+    assert!(!block.collides_with(at(0), opaque, &block, at(8), opaque));
+    assert!(block.collides_with(at(0), opaque, &block, at(7), opaque));
+}
+
+#[test]
+fn flipped_sprites_collide_where_drawn() {
+    let data = collision_sheet();
+    let sheet = SpriteSheet::new(&data, Size::new(8, 8)).unwrap();
+    let line = sheet.sprite(0, 0).unwrap();
+    let flipped = SpriteOptions {
+        flip_x: true,
+        ..SOLID_ON
+    };
+
+    // flipped, the column is on the right edge
+    let (a, b) = (Point::new(0, 0), Point::new(7, 3));
+    assert!(!line.collides_with(a, SOLID_ON, &line, b, SOLID_ON));
+    assert!(line.collides_with(a, flipped, &line, b, SOLID_ON));
+    assert!(!line.collides_with(a, flipped, &line, b, flipped));
+}
+
+#[test]
+fn rotated_sprites_collide_where_drawn() {
+    let data = collision_sheet();
+    let sheet = SpriteSheet::new(&data, Size::new(8, 8)).unwrap();
+    let line = sheet.sprite(0, 0).unwrap();
+    let rotated = SpriteOptions {
+        rotation: Rotation::R90,
+        ..SOLID_ON
+    };
+
+    // turned clockwise the column becomes the top row, crossing the other column
+    let (a, b) = (Point::new(0, 0), Point::new(5, 0));
+    assert!(!line.collides_with(a, SOLID_ON, &line, b, SOLID_ON));
+    assert!(line.collides_with(a, rotated, &line, b, SOLID_ON));
+    // below the top row only the column is left
+    assert!(!line.collides_with(a, rotated, &line, Point::new(5, 1), SOLID_ON));
+    assert!(line.collides_with(a, rotated, &line, Point::new(5, -7), SOLID_ON));
+}
+
+#[test]
+fn masks_limit_collisions() {
+    // all On, the mask only keeps the left column of each frame
+    let data = bmp(16, 8, BOTTOM_UP, |_, _| true);
+    let mask = bmp(16, 8, BOTTOM_UP, |x, _| x % 8 == 0);
+    let solid = SpriteSheet::new(&data, Size::new(8, 8)).unwrap();
+    let masked = SpriteSheet::new(&data, Size::new(8, 8))
+        .unwrap()
+        .with_mask(&mask)
+        .unwrap();
+    let opaque = SpriteOptions::default();
+    let (a, b) = (Point::new(0, 0), Point::new(4, 2));
+
+    let (solid, masked) = (solid.sprite(0, 0).unwrap(), masked.sprite(1, 0).unwrap());
+    assert!(solid.collides_with(a, opaque, &solid, b, opaque));
+    assert!(!masked.collides_with(a, opaque, &masked, b, opaque));
+    // the unmasked sprite covers the masked one's column
+    assert!(solid.collides_with(a, opaque, &masked, b, opaque));
+    assert!(!masked.collides_with(a, opaque, &solid, b, opaque));
+    assert!(masked.collides_with(a, opaque, &masked, Point::new(0, 6), opaque));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "needs Sprite::draw_blended")]
+fn draw_rejects_modes_that_read_the_target() {
+    let sheet = SpriteSheet::new(ELECTRIC, Size::new(64, 64)).unwrap();
+    let options = SpriteOptions {
+        blend: BlendMode::Xor,
+        ..SpriteOptions::default()
+    };
+    let mut fb = Framebuffer::new();
+    sheet
+        .sprite(0, 0)
+        .unwrap()
+        .draw(&mut fb, Point::zero(), options)
+        .unwrap();
+}
+
+#[test]
+fn draw_blended_inverts_the_target() {
+    let data = collision_sheet();
+    let sheet = SpriteSheet::new(&data, Size::new(8, 8)).unwrap();
+    let line = sheet.sprite(0, 0).unwrap();
+    let before = background();
+    for blend in [BlendMode::Xor, BlendMode::Invert] {
+        let options = SpriteOptions {
+            blend,
+            ..SpriteOptions::default()
+        };
+        let mut fb = before.clone();
+        line.draw_blended(&mut fb, Point::new(3, 3), options)
+            .unwrap();
+        for p in Rectangle::new(Point::new(3, 3), Size::new(8, 8)).points() {
+            // Xor only inverts under the On column, Invert under the whole frame
+            let inverted = blend == BlendMode::Invert || p.x == 3;
+            let want = before
+                .pixel(p)
+                .map(|c| if inverted { c.invert() } else { c });
+            assert_eq!(fb.pixel(p), want, "{blend:?} at {p}");
+        }
+    }
+}
+
+#[test]
+fn sheets_must_be_1bpp() {
+    let mut data = bmp(16, 16, BOTTOM_UP, noise);
+    // claim 4 bits per pixel
+    data[28] = 4;
+    assert!(SpriteSheet::new(&data, Size::new(8, 8)).is_err());
+}
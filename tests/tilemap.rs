@@ -0,0 +1,214 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Cell lookups, collision flags and drawing of a small tilemap, the drawn map is
+//! compared against references in `tests/snapshots/`.
+
+use embedded_graphics::{
+    image::GetPixel, pixelcolor::BinaryColor, prelude::*, primitives::Rectangle,
+};
+use kywy::engine::camera::Camera;
+use kywy::engine::sprite::SpriteSheet;
+use kywy::engine::tilemap::{TileFlags, Tilemap};
+use kywy::framebuffer::Framebuffer;
+use kywy::simulator::snapshot::assert_rendered;
+
+/// Bricks, solid, checkers and a platform, 16x16 each
+const TILES: &[u8] = include_bytes!("fixtures/tiles.bmp");
+
+const COLUMNS: u32 = 8;
+
+/// 8x6 cells, tile 9 is past the end of the sheet
+#[rustfmt::skip]
+const MAP: [u16; 48] = [
+    0, 0, 0, 0, 0, 0, 0, 3,
+    0, 0, 4, 4, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 3, 0, 0,
+    0, 0, 0, 0, 0, 2, 0, 0,
+    1, 1, 1, 0, 0, 1, 1, 1,
+    2, 2, 2, 9, 0, 2, 2, 2,
+];
+
+const WATER: TileFlags = TileFlags::flag(5);
+
+fn tileset() -> SpriteSheet<'static> {
+    SpriteSheet::new(TILES, Size::new(16, 16)).unwrap()
+}
+
+/// Bricks and solid tiles are solid, checkers are water and the platform is a platform.
+fn flags() -> Vec<TileFlags> {
+    MAP.iter()
+        .map(|tile| match tile {
+            1 | 2 => TileFlags::SOLID,
+            3 => WATER,
+            4 => TileFlags::PLATFORM,
+            _ => TileFlags::NONE,
+        })
+        .collect()
+}
+
+fn map<'a>(
+    tileset: &'a SpriteSheet<'a>,
+    position: Point,
+) -> Tilemap<'a, &'a [u16], Vec<TileFlags>> {
+    Tilemap::new(tileset, COLUMNS, &MAP[..])
+        .with_flags(flags())
+        .with_position(position)
+}
+
+fn rect(x: i32, y: i32, width: u32, height: u32) -> Rectangle {
+    Rectangle::new(Point::new(x, y), Size::new(width, height))
+}
+
+#[test]
+fn cells_outside_the_map() {
+    let tileset = tileset();
+    let map = map(&tileset, Point::new(10, 20));
+    assert_eq!((map.columns(), map.rows()), (8, 6));
+    assert_eq!(map.bounds(), rect(10, 20, 128, 96));
+
+    assert_eq!(map.cell_at(Point::new(10, 20)), Some((0, 0)));
+    assert_eq!(map.cell_at(Point::new(137, 115)), Some((7, 5)));
+    for outside in [
+        (9, 20),
+        (10, 19),
+        (138, 20),
+        (10, 116),
+        (-500, -500),
+        (1000, 50),
+    ] {
+        let p = Point::new(outside.0, outside.1);
+        assert_eq!(map.cell_at(p), None, "{p}");
+        assert_eq!(map.tile_at(p), None, "{p}");
+        assert_eq!(map.flags_at(p), TileFlags::NONE, "{p}");
+    }
+    assert_eq!(map.tile(8, 0), None);
+    assert_eq!(map.tile(0, 6), None);
+    assert_eq!(map.tile(u32::MAX, u32::MAX), None);
+    assert_eq!(map.flags(8, 5), TileFlags::NONE);
+
+    // a short flag layer leaves the rest of the cells without flags
+    let flags = flags();
+    let short = Tilemap::new(&tileset, COLUMNS, &MAP[..]).with_flags(&flags[..40]);
+    assert_eq!(short.flags(7, 4), TileFlags::SOLID);
+    assert_eq!(short.flags(0, 5), TileFlags::NONE);
+    // and a partial last row is not part of the map
+    let partial = Tilemap::<&[u16], &[TileFlags]>::new(&tileset, COLUMNS, &MAP[..44]);
+    assert_eq!(partial.rows(), 5);
+    assert_eq!(partial.tile(0, 5), None);
+}
+
+#[test]
+fn negative_coordinates() {
+    let tileset = tileset();
+    let map = map(&tileset, Point::new(-40, -24));
+    assert_eq!(map.cell_at(Point::new(-40, -24)), Some((0, 0)));
+    assert_eq!(map.cell_at(Point::new(-1, -1)), Some((2, 1)));
+    assert_eq!(map.cell_at(Point::new(0, 0)), Some((2, 1)));
+    assert_eq!(map.cell_at(Point::new(-41, 0)), None);
+    assert_eq!(map.tile_at(Point::new(-1, -1)), Some(4));
+    assert_eq!(map.cell_area(2, 1), rect(-8, -8, 16, 16));
+
+    let cells: Vec<_> = map.cells_in(rect(-100, -100, 80, 90)).collect();
+    assert_eq!(cells, [(0, 0), (1, 0)]);
+}
+
+#[test]
+fn cells_in_areas_across_tile_edges() {
+    let tileset = tileset();
+    let map = map(&tileset, Point::new(10, 20));
+    let cells = |area| map.cells_in(area).collect::<Vec<_>>();
+
+    // exactly one tile, then one pixel more each way
+    assert_eq!(cells(rect(26, 36, 16, 16)), [(1, 1)]);
+    assert_eq!(cells(rect(26, 36, 17, 16)), [(1, 1), (2, 1)]);
+    assert_eq!(
+        cells(rect(25, 36, 16, 17)),
+        [(0, 1), (1, 1), (0, 2), (1, 2)]
+    );
+    // a single pixel on each side of a corner
+    assert_eq!(cells(rect(25, 35, 2, 2)), [(0, 0), (1, 0), (0, 1), (1, 1)]);
+    assert_eq!(cells(rect(26, 36, 1, 1)), [(1, 1)]);
+    // hanging over the edges only counts the cells inside
+    assert_eq!(cells(rect(0, 100, 20, 100)), [(0, 5)]);
+    assert_eq!(cells(rect(130, 0, 100, 30)), [(7, 0)]);
+    assert_eq!(cells(rect(-1000, -1000, 3000, 3000)).len(), 48);
+    // nothing for areas off the map or without pixels
+    assert!(cells(rect(138, 20, 10, 10)).is_empty());
+    assert!(cells(rect(0, 0, 10, 200)).is_empty());
+    assert!(cells(rect(30, 30, 0, 10)).is_empty());
+}
+
+#[test]
+fn collisions_follow_the_flags() {
+    let tileset = tileset();
+    let map = map(&tileset, Point::new(10, 20));
+    let solid = TileFlags::SOLID;
+    // the ground starts at y = 84, the column on the right at x = 90
+    let ground = 20 + 4 * 16;
+    assert!(!map.collides(rect(10, ground - 16, 16, 16), solid));
+    assert!(map.collides(rect(10, ground - 15, 16, 16), solid));
+    assert!(!map.collides(rect(74, ground - 16, 16, 16), solid));
+    assert!(map.collides(rect(75, ground - 16, 16, 16), solid));
+    // the gap in the ground lets things fall through
+    assert!(!map.collides(rect(58, ground, 32, 32), solid));
+    assert!(map.collides(rect(57, ground, 32, 32), solid));
+
+    // platforms and game flags are separate from solid tiles
+    let platform = rect(42, 36, 32, 1);
+    assert!(!map.collides(platform, solid));
+    assert!(map.collides(platform, TileFlags::PLATFORM));
+    assert!(map.collides(platform, TileFlags::PLATFORM | WATER));
+    assert_eq!(map.flags_at(Point::new(130, 20)), WATER);
+    assert!(map.flags(5, 2).contains(WATER));
+    assert!(!map.flags(5, 2).intersects(solid | TileFlags::PLATFORM));
+    assert!(!map.collides(map.bounds(), TileFlags::flag(7)));
+    // outside the map nothing collides
+    assert!(!map.collides(rect(0, 0, 10, 200), TileFlags(u8::MAX)));
+}
+
+#[test]
+fn changing_cells() {
+    let tileset = tileset();
+    let mut tiles = MAP;
+    let mut flags = flags();
+    let mut map = Tilemap::new(&tileset, COLUMNS, &mut tiles[..]).with_flags(&mut flags[..]);
+    assert!(map.set_tile(3, 4, 1));
+    assert!(map.set_flags(3, 4, TileFlags::SOLID));
+    assert_eq!(map.tile(3, 4), Some(1));
+    assert!(map.collides(rect(48, 64, 16, 16), TileFlags::SOLID));
+    assert!(!map.set_tile(8, 0, 1));
+    assert!(!map.set_flags(0, 6, TileFlags::SOLID));
+}
+
+#[test]
+fn tilemap() {
+    let tileset = tileset();
+    let map = map(&tileset, Point::new(8, 36));
+    assert_rendered("tilemap", |fb| map.draw(fb).unwrap());
+}
+
+#[test]
+fn tilemap_through_camera() {
+    let tileset = tileset();
+    let map = map(&tileset, Point::new(8, 36));
+    let mut camera = Camera::new(Size::new(100, 70)).with_bounds(map.bounds());
+    camera.look_at(Point::new(110, 110));
+    assert_eq!(camera.position, Point::new(36, 62));
+
+    let mut fb = Framebuffer::new();
+    fb.fill(BinaryColor::On);
+    map.draw(&mut fb).unwrap();
+    assert_rendered("tilemap_camera", |view_fb| {
+        map.draw(&mut camera.view(view_fb)).unwrap();
+        // the same pixels as drawing the whole map, moved by the camera
+        for p in view_fb.bounding_box().points() {
+            let want = if Rectangle::new(Point::zero(), camera.size()).contains(p) {
+                fb.pixel(camera.to_world(p))
+            } else {
+                Some(BinaryColor::On)
+            };
+            assert_eq!(view_fb.pixel(p), want, "{p}");
+        }
+    });
+}
@@ -0,0 +1,163 @@
+// SPDX-FileCopyrightText: 2025 KOINSLOT Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Every transition starts at the old screen, ends at the new one and only ever
+//! uncovers more of the new screen in between.
+
+use embedded_graphics::pixelcolor::BinaryColor;
+use kywy::engine::transition::{Direction, PROGRESS_END, Transition, mix};
+use kywy::framebuffer::{DisplayRotation, Framebuffer};
+
+const TRANSITIONS: [Transition; 11] = [
+    Transition::Wipe(Direction::Left),
+    Transition::Wipe(Direction::Right),
+    Transition::Wipe(Direction::Up),
+    Transition::Wipe(Direction::Down),
+    Transition::Slide(Direction::Left),
+    Transition::Slide(Direction::Right),
+    Transition::Slide(Direction::Up),
+    Transition::Slide(Direction::Down),
+    Transition::Dissolve,
+    Transition::Checkerboard,
+    Transition::Iris,
+];
+
+fn filled(color: BinaryColor) -> Framebuffer {
+    let mut fb = Framebuffer::new();
+    fb.fill(color);
+    fb
+}
+
+/// A screen with some detail, so copies from the wrong place show up.
+fn pattern(seed: usize) -> Framebuffer {
+    let mut fb = Framebuffer::new();
+    for y in 0..fb.height() {
+        for x in 0..fb.width() {
+            let on = (x * 7 + y * 13 + seed) % 5 < 2;
+            fb.set_pixel(x, y, BinaryColor::from(on));
+        }
+    }
+    fb
+}
+
+fn count_on(fb: &Framebuffer) -> usize {
+    // On pixels are set bits, whatever the orientation
+    fb.as_bytes().iter().map(|b| b.count_ones() as usize).sum()
+}
+
+#[test]
+fn start_is_from_and_end_is_to() {
+    let (from, to) = (pattern(0), pattern(3));
+    for transition in TRANSITIONS {
+        let mut out = Framebuffer::new();
+        mix(&from, &to, &mut out, transition, 0);
+        assert_eq!(out.as_bytes(), from.as_bytes(), "{transition:?} at 0");
+        mix(&from, &to, &mut out, transition, PROGRESS_END);
+        assert_eq!(out.as_bytes(), to.as_bytes(), "{transition:?} at end");
+    }
+}
+
+#[test]
+fn new_screen_only_grows() {
+    // with a black old and a white new screen, white pixels count the progress
+    let (from, to) = (filled(BinaryColor::Off), filled(BinaryColor::On));
+    for transition in TRANSITIONS {
+        let mut out = Framebuffer::new();
+        let mut previous = 0;
+        for progress in 0..=PROGRESS_END {
+            mix(&from, &to, &mut out, transition, progress);
+            let on = count_on(&out);
+            assert!(
+                on >= previous,
+                "{transition:?} went back from {previous} to {on} at {progress}"
+            );
+            previous = on;
+        }
+    }
+}
+
+#[test]
+fn midpoint_shows_both_screens() {
+    let (from, to) = (filled(BinaryColor::Off), filled(BinaryColor::On));
+    let total = from.width() * from.height();
+    let mid = PROGRESS_END / 2;
+    for transition in TRANSITIONS {
+        let mut out = Framebuffer::new();
+        mix(&from, &to, &mut out, transition, mid);
+        let on = count_on(&out);
+        assert!(0 < on && on < total, "{transition:?} shows {on} new pixels");
+
+        // going either way from the midpoint moves in the right direction
+        mix(&from, &to, &mut out, transition, mid - 16);
+        assert!(count_on(&out) <= on, "{transition:?} before the midpoint");
+        mix(&from, &to, &mut out, transition, mid + 16);
+        assert!(count_on(&out) >= on, "{transition:?} after the midpoint");
+    }
+}
+
+#[test]
+fn linear_transitions_are_half_done_at_midpoint() {
+    let (from, to) = (filled(BinaryColor::Off), filled(BinaryColor::On));
+    let total = from.width() * from.height();
+    for transition in TRANSITIONS {
+        if matches!(transition, Transition::Iris) {
+            // a growing circle covers the screen faster than the progress
+            continue;
+        }
+        let mut out = Framebuffer::new();
+        mix(&from, &to, &mut out, transition, PROGRESS_END / 2);
+        let on = count_on(&out);
+        // the checkerboard fills whole rows of its squares, an eighth of them at a time
+        assert!(
+            on.abs_diff(total / 2) <= total / 10,
+            "{transition:?} shows {on} of {total}"
+        );
+    }
+}
+
+#[test]
+fn slide_moves_the_screens() {
+    let (from, to) = (pattern(0), pattern(3));
+    let mut out = Framebuffer::new();
+    mix(
+        &from,
+        &to,
+        &mut out,
+        Transition::Slide(Direction::Left),
+        PROGRESS_END / 2,
+    );
+    // old pixels move left by about half of the width and the new screen follows
+    let offset = from.width() * (PROGRESS_END / 2) as usize / PROGRESS_END as usize;
+    for y in 0..out.height() {
+        for x in 0..out.width() {
+            let expected = if x + offset < from.width() {
+                from.get_pixel(x + offset, y)
+            } else {
+                to.get_pixel(x + offset - from.width(), y)
+            };
+            assert_eq!(out.get_pixel(x, y), expected, "at {x}, {y}");
+        }
+    }
+}
+
+#[test]
+fn rotated_buffers_use_drawing_orientation() {
+    let (mut from, mut to) = (filled(BinaryColor::Off), filled(BinaryColor::On));
+    let mut out = Framebuffer::new();
+    for fb in [&mut from, &mut to, &mut out] {
+        fb.set_rotation(DisplayRotation::Rotate90);
+    }
+    mix(
+        &from,
+        &to,
+        &mut out,
+        Transition::Wipe(Direction::Right),
+        PROGRESS_END / 2,
+    );
+    // 168 wide in landscape, the left 83 columns are new
+    let edge = out.width() * (PROGRESS_END / 2) as usize / PROGRESS_END as usize;
+    for x in [0, edge - 1, edge, out.width() - 1] {
+        let expected = BinaryColor::from(x < edge);
+        assert_eq!(out.get_pixel(x, 10), Some(expected), "column {x}");
+    }
+}